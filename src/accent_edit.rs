@@ -0,0 +1,85 @@
+use crate::models::AccentPhrase;
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+/// アクセント句への編集操作。エディタでの操作と同じ結果になるようにしている。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AccentPhraseOperation {
+    /// `mora_index`番目のモーラの手前で句を分ける。
+    Split {
+        accent_phrase_index: usize,
+        mora_index: usize,
+    },
+    /// 句を次の句と繋げる。
+    Merge {
+        accent_phrase_index: usize,
+    },
+    /// アクセント核の位置を変える。1始まり。
+    SetAccent {
+        accent_phrase_index: usize,
+        accent: u32,
+    },
+    ToggleInterrogative {
+        accent_phrase_index: usize,
+    },
+}
+
+fn get_mut(accent_phrases: &mut [AccentPhrase], index: usize) -> anyhow::Result<&mut AccentPhrase> {
+    accent_phrases
+        .get_mut(index)
+        .with_context(|| format!("アクセント句が見つかりません：{}", index))
+}
+
+pub fn apply(accent_phrases: &mut Vec<AccentPhrase>, operation: &AccentPhraseOperation) -> anyhow::Result<()> {
+    match *operation {
+        AccentPhraseOperation::Split {
+            accent_phrase_index,
+            mora_index,
+        } => {
+            let accent_phrase = get_mut(accent_phrases, accent_phrase_index)?;
+            if mora_index == 0 || mora_index >= accent_phrase.moras.len() {
+                bail!("分割する位置が不正です：{}", mora_index);
+            }
+            let accent = accent_phrase.accent as usize;
+            let second = AccentPhrase {
+                moras: accent_phrase.moras.split_off(mora_index),
+                accent: if accent > mora_index {
+                    (accent - mora_index) as u32
+                } else {
+                    1
+                },
+                pause_mora: accent_phrase.pause_mora.take(),
+                is_interrogative: accent_phrase.is_interrogative,
+            };
+            accent_phrase.accent = accent.min(mora_index) as u32;
+            accent_phrase.is_interrogative = false;
+            accent_phrases.insert(accent_phrase_index + 1, second);
+        }
+        AccentPhraseOperation::Merge { accent_phrase_index } => {
+            if accent_phrase_index + 1 >= accent_phrases.len() {
+                bail!("次のアクセント句がありません：{}", accent_phrase_index);
+            }
+            let second = accent_phrases.remove(accent_phrase_index + 1);
+            let first = &mut accent_phrases[accent_phrase_index];
+            first.moras.extend(second.moras);
+            first.pause_mora = second.pause_mora;
+            first.is_interrogative = second.is_interrogative;
+        }
+        AccentPhraseOperation::SetAccent {
+            accent_phrase_index,
+            accent,
+        } => {
+            let accent_phrase = get_mut(accent_phrases, accent_phrase_index)?;
+            if accent == 0 || accent as usize > accent_phrase.moras.len() {
+                bail!("アクセントの位置が不正です：{}", accent);
+            }
+            accent_phrase.accent = accent;
+        }
+        AccentPhraseOperation::ToggleInterrogative { accent_phrase_index } => {
+            let accent_phrase = get_mut(accent_phrases, accent_phrase_index)?;
+            accent_phrase.is_interrogative = !accent_phrase.is_interrogative;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use crate::models::{AccentPhrase, AudioQuery, Mora};
+
+/// 分割した一塊の最大文字数。
+pub static MAX_CHUNK_LENGTH: usize = 200;
+/// 分割した一塊のおおよその最大モーラ数。
+pub static MAX_CHUNK_MORA_COUNT: usize = 300;
+
+static SENTENCE_DELIMITERS: &[char] = &['。', '！', '？', '!', '?', '\n'];
+static CLAUSE_DELIMITERS: &[char] = &['、', '，', ','];
+
+/// `delimiters`の直後で区切る。区切り文字は前の塊に残す。
+fn split_after<'a>(text: &'a str, delimiters: &[char]) -> Vec<&'a str> {
+    let mut pieces = vec![];
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        if delimiters.contains(&c) {
+            let end = index + c.len_utf8();
+            pieces.push(&text[start..end]);
+            start = end;
+        }
+    }
+    pieces.push(&text[start..]);
+    pieces
+}
+
+/// 短い塊をつなげて、なるべく`MAX_CHUNK_LENGTH`以下の塊にする。
+fn pack(pieces: Vec<&str>, joiner: &str) -> Vec<String> {
+    let mut chunks: Vec<String> = vec![];
+    for piece in pieces {
+        match chunks.last_mut() {
+            Some(last) if last.chars().count() + piece.chars().count() <= MAX_CHUNK_LENGTH => {
+                last.push_str(joiner);
+                last.push_str(piece);
+            }
+            _ => chunks.push(piece.to_string()),
+        }
+    }
+    chunks
+}
+
+/// 長いテキストを文単位で分ける。文が長すぎる場合は読点で、それでも長ければ文字数で分ける。
+///
+/// `is_kana`のときは、AquesTalk風記法を壊さないよう`、`でのみ分ける。
+pub fn split_text(text: &str, is_kana: bool) -> Vec<String> {
+    if is_kana {
+        let pieces: Vec<&str> = text.split('、').filter(|piece| !piece.is_empty()).collect();
+        return pack(pieces, "、");
+    }
+
+    let mut chunks = vec![];
+    for sentence in split_after(text, SENTENCE_DELIMITERS) {
+        let sentence = sentence.trim();
+        if sentence.is_empty() {
+            continue;
+        }
+        if sentence.chars().count() <= MAX_CHUNK_LENGTH {
+            chunks.push(sentence.to_string());
+            continue;
+        }
+        for clause in pack(split_after(sentence, CLAUSE_DELIMITERS), "") {
+            let chars: Vec<char> = clause.chars().collect();
+            chunks.extend(
+                chars
+                    .chunks(MAX_CHUNK_LENGTH)
+                    .map(|chars| chars.iter().collect::<String>()),
+            );
+        }
+    }
+    chunks
+}
+
+fn pause_mora(length: f32) -> Mora {
+    Mora {
+        text: "、".to_string(),
+        consonant: None,
+        consonant_length: None,
+        vowel: "pau".to_string(),
+        vowel_length: length,
+        pitch: 0.0,
+    }
+}
+
+/// 塊ごとのアクセント句を、間に`pause_length`秒の無音を挟んでつなげる。
+pub fn join_accent_phrases(chunks: Vec<Vec<AccentPhrase>>, pause_length: f32) -> Vec<AccentPhrase> {
+    let chunk_count = chunks.len();
+    let mut accent_phrases = vec![];
+    for (i, mut chunk) in chunks.into_iter().enumerate() {
+        if i + 1 < chunk_count {
+            if let Some(last) = chunk.last_mut() {
+                last.pause_mora = Some(pause_mora(pause_length));
+            }
+        }
+        accent_phrases.extend(chunk);
+    }
+    accent_phrases
+}
+
+/// 一つの塊とその後に入れる無音（秒）。
+pub struct QueryChunk {
+    pub audio_query: AudioQuery,
+    pub gap: f32,
+}
+
+/// AudioQueryをポーズの位置で`MAX_CHUNK_MORA_COUNT`モーラ程度ずつに分ける。
+///
+/// ポーズは取り除き、その長さ（`pause_length`があればそれ）を塊の間の無音にする。
+pub fn split_audio_query(audio_query: &AudioQuery, pause_length: Option<f32>) -> Vec<QueryChunk> {
+    let speed_scale = if audio_query.speed_scale > 0.0 {
+        audio_query.speed_scale
+    } else {
+        1.0
+    };
+    let new_chunk = |accent_phrases: Vec<AccentPhrase>| AudioQuery {
+        accent_phrases,
+        speed_scale: audio_query.speed_scale,
+        pitch_scale: audio_query.pitch_scale,
+        intonation_scale: audio_query.intonation_scale,
+        volume_scale: audio_query.volume_scale,
+        pre_phoneme_length: 0.0,
+        post_phoneme_length: 0.0,
+        output_sampling_rate: audio_query.output_sampling_rate,
+        output_stereo: audio_query.output_stereo,
+        kana: String::new(),
+    };
+
+    let mut chunks = vec![];
+    let mut current = vec![];
+    let mut mora_count = 0;
+    for accent_phrase in &audio_query.accent_phrases {
+        let mut accent_phrase = accent_phrase.clone();
+        mora_count += accent_phrase.moras.len();
+        // ポーズが無いまま長くなりすぎたら、句の切れ目で分ける。
+        let cut = match &accent_phrase.pause_mora {
+            Some(pause) if mora_count >= MAX_CHUNK_MORA_COUNT => {
+                Some(pause_length.unwrap_or(pause.vowel_length / speed_scale))
+            }
+            _ if mora_count >= MAX_CHUNK_MORA_COUNT * 2 => Some(0.0),
+            _ => None,
+        };
+        if cut.is_some() {
+            accent_phrase.pause_mora = None;
+        }
+        current.push(accent_phrase);
+        if let Some(gap) = cut {
+            chunks.push(QueryChunk {
+                audio_query: new_chunk(std::mem::take(&mut current)),
+                gap,
+            });
+            mora_count = 0;
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(QueryChunk {
+            audio_query: new_chunk(current),
+            gap: 0.0,
+        });
+    }
+
+    if let Some(first) = chunks.first_mut() {
+        first.audio_query.pre_phoneme_length = audio_query.pre_phoneme_length;
+    }
+    if let Some(last) = chunks.last_mut() {
+        last.audio_query.post_phoneme_length = audio_query.post_phoneme_length;
+        last.gap = 0.0;
+    }
+    chunks
+}
@@ -0,0 +1,406 @@
+use crate::{kana::to_katakana, models::WordType};
+use anyhow::{bail, ensure, Context};
+use serde::{Deserialize, Serialize};
+
+/// ユーザー辞書の入出力形式。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DictFormat {
+    /// `表記,読み,アクセント型,優先度,品詞`。3列目以降は省略できる。
+    Csv,
+    /// 列はCSVと同じで、タブ区切り。
+    Tsv,
+    /// MeCab（OpenJTalk）のシステム辞書と同じCSV。
+    Mecab,
+    /// 棒読みちゃんなどが使う`表記<TAB>読み`の形式。読みのアクセント核の直後に`'`を置ける。
+    Text,
+}
+
+impl DictFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            DictFormat::Csv | DictFormat::Mecab => "text/csv; charset=utf-8",
+            DictFormat::Tsv => "text/tab-separated-values; charset=utf-8",
+            DictFormat::Text => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+/// 辞書ファイルの一行分の単語。省略された項目は`None`。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    pub surface: String,
+    pub pronunciation: String,
+    pub accent_type: Option<usize>,
+    pub priority: Option<u32>,
+    pub word_type: Option<WordType>,
+}
+
+/// CSVの見出し行の1列目として扱う文字列。
+static HEADERS: &[&str] = &["surface", "表記", "単語"];
+
+const CSV_HEADER: &str = "surface,pronunciation,accent_type,priority,word_type";
+
+/// 品詞ごとの、MeCabの品詞・文脈ID・優先度0から10に対応するコスト。
+/// VOICEVOX ENGINEのユーザー辞書と同じ値にしている。
+struct PartOfSpeech {
+    word_type: WordType,
+    part_of_speech: [&'static str; 4],
+    context_id: i32,
+    costs: [i32; 11],
+}
+
+static PARTS_OF_SPEECH: &[PartOfSpeech] = &[
+    PartOfSpeech {
+        word_type: WordType::ProperNoun,
+        part_of_speech: ["名詞", "固有名詞", "一般", "*"],
+        context_id: 1348,
+        costs: [14176, 9110, 8984, 8859, 8734, 8609, 7328, 6048, 4768, 3488, -988],
+    },
+    PartOfSpeech {
+        word_type: WordType::CommonNoun,
+        part_of_speech: ["名詞", "一般", "*", "*"],
+        context_id: 1345,
+        costs: [15742, 8979, 8170, 7362, 6554, 5746, 4321, 2897, 1473, 49, -4445],
+    },
+    PartOfSpeech {
+        word_type: WordType::Verb,
+        part_of_speech: ["動詞", "自立", "*", "*"],
+        context_id: 642,
+        costs: [13999, 8771, 8318, 7866, 7414, 6962, 6761, 6561, 6360, 6160, 3100],
+    },
+    PartOfSpeech {
+        word_type: WordType::Adjective,
+        part_of_speech: ["形容詞", "自立", "*", "*"],
+        context_id: 20,
+        costs: [10001, 7250, 6549, 5849, 5149, 4449, 4153, 3857, 3561, 3266, 1527],
+    },
+    PartOfSpeech {
+        word_type: WordType::Suffix,
+        part_of_speech: ["名詞", "接尾", "一般", "*"],
+        context_id: 1358,
+        costs: [15847, 13622, 12228, 10834, 9440, 8047, 7378, 6710, 6041, 5373, 4399],
+    },
+];
+
+fn part_of_speech(word_type: WordType) -> &'static PartOfSpeech {
+    PARTS_OF_SPEECH
+        .iter()
+        .find(|part_of_speech| part_of_speech.word_type == word_type)
+        .unwrap()
+}
+
+/// MeCabの品詞から品詞を決める。名詞の細分類が分からないものは普通名詞にする。
+fn word_type_of(fields: &[String]) -> Option<WordType> {
+    match (fields[0].as_str(), fields[1].as_str()) {
+        ("名詞", "固有名詞") => Some(WordType::ProperNoun),
+        ("名詞", "接尾") => Some(WordType::Suffix),
+        ("名詞", _) => Some(WordType::CommonNoun),
+        ("動詞", _) => Some(WordType::Verb),
+        ("形容詞", _) => Some(WordType::Adjective),
+        _ => None,
+    }
+}
+
+/// コストに一番近い優先度。
+fn priority_of(word_type: WordType, cost: i32) -> u32 {
+    part_of_speech(word_type)
+        .costs
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, candidate)| (**candidate - cost).abs())
+        .map(|(priority, _)| priority as u32)
+        .unwrap()
+}
+
+/// CSVの一行を列に分ける。`"`で囲まれた列の中の区切り文字と`""`を扱う。
+fn split_fields(line: &str, delimiter: char) -> anyhow::Result<Vec<String>> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    ensure!(!quoted, "\"が閉じられていません。");
+    fields.push(field);
+    Ok(fields.into_iter().map(|field| field.trim().to_string()).collect())
+}
+
+fn join_fields(fields: &[String], delimiter: char) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains([delimiter, '"', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string())
+}
+
+fn parse_optional<T: std::str::FromStr>(fields: &[String], index: usize, name: &str) -> anyhow::Result<Option<T>> {
+    match fields.get(index).map(|field| field.as_str()) {
+        None | Some("") => Ok(None),
+        Some(field) => field
+            .parse()
+            .ok()
+            .with_context(|| format!("{}が数値ではありません：{}", name, field))
+            .map(Some),
+    }
+}
+
+fn parse_word_type(field: &str) -> anyhow::Result<WordType> {
+    serde_json::from_value(serde_json::Value::String(field.to_uppercase()))
+        .ok()
+        .with_context(|| format!("品詞が不正です：{}", field))
+}
+
+fn word_type_name(word_type: WordType) -> String {
+    serde_json::to_value(word_type).unwrap().as_str().unwrap().to_string()
+}
+
+fn parse_separated(line: &str, delimiter: char) -> anyhow::Result<Row> {
+    let fields = split_fields(line, delimiter)?;
+    ensure!(fields.len() >= 2, "表記と読みが必要です。");
+    Ok(Row {
+        surface: fields[0].clone(),
+        pronunciation: fields[1].clone(),
+        accent_type: parse_optional(&fields, 2, "アクセント型")?,
+        priority: parse_optional(&fields, 3, "優先度")?,
+        word_type: match fields.get(4).map(|field| field.as_str()) {
+            None | Some("") => None,
+            Some(field) => Some(parse_word_type(field)?),
+        },
+    })
+}
+
+/// 表記,左文脈ID,右文脈ID,コスト,品詞,品詞細分類1,品詞細分類2,品詞細分類3,活用型,活用形,原形,読み,発音,アクセント型/モーラ数,アクセント結合規則
+fn parse_mecab(line: &str) -> anyhow::Result<Row> {
+    let fields = split_fields(line, ',')?;
+    ensure!(fields.len() >= 13, "MeCabの辞書の列が足りません。");
+    let Some(word_type) = word_type_of(&fields[4..8]) else {
+        bail!("対応していない品詞です：{}", fields[4..8].join(","));
+    };
+    let cost: i32 = fields[3]
+        .parse()
+        .ok()
+        .with_context(|| format!("コストが数値ではありません：{}", fields[3]))?;
+    let pronunciation = if fields[12] == "*" { &fields[11] } else { &fields[12] };
+    let accent_type = match fields.get(13).and_then(|field| field.split('/').next()) {
+        None | Some("*") | Some("") => None,
+        Some(accent_type) => Some(
+            accent_type
+                .parse()
+                .ok()
+                .with_context(|| format!("アクセント型が数値ではありません：{}", fields[13]))?,
+        ),
+    };
+    Ok(Row {
+        surface: fields[0].clone(),
+        pronunciation: pronunciation.clone(),
+        accent_type,
+        priority: Some(priority_of(word_type, cost)),
+        word_type: Some(word_type),
+    })
+}
+
+fn parse_text(line: &str, count_moras: impl Fn(&str) -> usize) -> anyhow::Result<Row> {
+    let Some((surface, pronunciation)) = line.split_once('\t').or_else(|| line.split_once(char::is_whitespace)) else {
+        bail!("表記と読みが必要です。");
+    };
+    let pronunciation = pronunciation.trim();
+    let accent_type = pronunciation
+        .find('\'')
+        .map(|index| count_moras(&to_katakana(&pronunciation[..index])));
+    Ok(Row {
+        surface: surface.trim().to_string(),
+        pronunciation: pronunciation.replace('\'', ""),
+        accent_type,
+        priority: None,
+        word_type: None,
+    })
+}
+
+/// 辞書ファイルを読む。空行・見出し行と、`Text`では`#`か`;`で始まるコメント行を飛ばし、行番号（1始まり）と結果を返す。
+/// ほかの形式では`#`で始まる表記もあり得るので、コメントとしては扱わない。
+/// `count_moras`はカタカナのモーラ数を数える関数。
+pub fn parse(format: DictFormat, text: &str, count_moras: impl Fn(&str) -> usize) -> Vec<(usize, anyhow::Result<Row>)> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut rows = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || (format == DictFormat::Text && line.starts_with(['#', ';'])) {
+            continue;
+        }
+        let first_field = line.split([',', '\t']).next().unwrap().trim();
+        if rows.is_empty() && HEADERS.contains(&first_field) {
+            continue;
+        }
+        let row = match format {
+            DictFormat::Csv => parse_separated(line, ','),
+            DictFormat::Tsv => parse_separated(line, '\t'),
+            DictFormat::Mecab => parse_mecab(line),
+            DictFormat::Text => parse_text(line, &count_moras),
+        };
+        rows.push((index + 1, row));
+    }
+    rows
+}
+
+/// 単語を辞書ファイルにする。`Row`の項目はすべて埋まっているものとし、`None`は既定値で書く。
+pub fn write(format: DictFormat, rows: &[Row], count_moras: impl Fn(&str) -> usize) -> String {
+    let mut lines = vec![];
+    if format == DictFormat::Csv {
+        lines.push(CSV_HEADER.to_string());
+    } else if format == DictFormat::Tsv {
+        lines.push(CSV_HEADER.replace(',', "\t"));
+    }
+    for row in rows {
+        let word_type = row.word_type.unwrap_or_default();
+        let accent_type = row.accent_type.unwrap_or(0);
+        let priority = row.priority.unwrap_or(5);
+        let line = match format {
+            DictFormat::Csv | DictFormat::Tsv => {
+                let delimiter = if format == DictFormat::Csv { ',' } else { '\t' };
+                join_fields(
+                    &[
+                        row.surface.clone(),
+                        row.pronunciation.clone(),
+                        accent_type.to_string(),
+                        priority.to_string(),
+                        word_type_name(word_type),
+                    ],
+                    delimiter,
+                )
+            }
+            DictFormat::Mecab => {
+                let part_of_speech = part_of_speech(word_type);
+                let mut fields = vec![
+                    row.surface.clone(),
+                    part_of_speech.context_id.to_string(),
+                    part_of_speech.context_id.to_string(),
+                    part_of_speech.costs[priority.min(10) as usize].to_string(),
+                ];
+                fields.extend(part_of_speech.part_of_speech.iter().map(|field| field.to_string()));
+                fields.extend([
+                    "*".to_string(),
+                    "*".to_string(),
+                    row.surface.clone(),
+                    row.pronunciation.clone(),
+                    row.pronunciation.clone(),
+                    format!("{}/{}", accent_type, count_moras(&row.pronunciation)),
+                    "*".to_string(),
+                ]);
+                join_fields(&fields, ',')
+            }
+            DictFormat::Text => {
+                // アクセント核の位置をモーラ数から文字数に直す。
+                let pronunciation = if accent_type == 0 {
+                    row.pronunciation.clone()
+                } else {
+                    let chars: Vec<char> = row.pronunciation.chars().collect();
+                    let index = (1..=chars.len())
+                        .rfind(|end| count_moras(&chars[..*end].iter().collect::<String>()) == accent_type)
+                        .unwrap_or(chars.len());
+                    format!(
+                        "{}'{}",
+                        chars[..index].iter().collect::<String>(),
+                        chars[index..].iter().collect::<String>()
+                    )
+                };
+                format!("{}\t{}", row.surface, pronunciation)
+            }
+        };
+        lines.push(line);
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kana::count_moras;
+
+    fn row(surface: &str, pronunciation: &str, accent_type: usize, priority: u32, word_type: WordType) -> Row {
+        Row {
+            surface: surface.to_string(),
+            pronunciation: pronunciation.to_string(),
+            accent_type: Some(accent_type),
+            priority: Some(priority),
+            word_type: Some(word_type),
+        }
+    }
+
+    fn rows() -> Vec<Row> {
+        vec![
+            row("ずんだもん", "ズンダモン", 1, 5, WordType::ProperNoun),
+            row("東北", "トーホク", 0, 8, WordType::CommonNoun),
+            row("ググる", "ググル", 2, 3, WordType::Verb),
+            row("#タグ", "タグ", 1, 5, WordType::CommonNoun),
+            row("a,\"b\"", "エービー", 3, 10, WordType::Suffix),
+        ]
+    }
+
+    fn round_trip(format: DictFormat, rows: &[Row]) -> Vec<Row> {
+        let text = write(format, rows, count_moras);
+        parse(format, &text, count_moras)
+            .into_iter()
+            .map(|(_, row)| row.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        assert_eq!(round_trip(DictFormat::Csv, &rows()), rows());
+    }
+
+    #[test]
+    fn tsv_round_trip() {
+        assert_eq!(round_trip(DictFormat::Tsv, &rows()), rows());
+    }
+
+    #[test]
+    fn mecab_round_trip() {
+        // MeCabの辞書は列をカンマで区切るので、表記にカンマを含むものは除く。
+        let rows: Vec<Row> = rows().into_iter().filter(|row| !row.surface.contains(',')).collect();
+        assert_eq!(round_trip(DictFormat::Mecab, &rows), rows);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        // `#`で始まる行はコメントになり、優先度と品詞は書かれない。平板型は`'`を置かないので省略になる。
+        let rows: Vec<Row> = rows()
+            .into_iter()
+            .filter(|row| !row.surface.starts_with('#'))
+            .map(|row| Row {
+                accent_type: row.accent_type.filter(|accent_type| *accent_type != 0),
+                priority: None,
+                word_type: None,
+                ..row
+            })
+            .collect();
+        assert_eq!(round_trip(DictFormat::Text, &rows), rows);
+    }
+
+    #[test]
+    fn comments_only_in_text() {
+        let text = "# コメント\n東北\tトーホク\n";
+        assert_eq!(parse(DictFormat::Text, text, count_moras).len(), 1);
+        let rows = parse(DictFormat::Csv, "#タグ,タグ\n", count_moras);
+        assert_eq!(rows[0].1.as_ref().unwrap().surface, "#タグ");
+    }
+}
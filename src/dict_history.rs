@@ -0,0 +1,175 @@
+use crate::models::VvUserDictWord;
+use anyhow::{bail, Context};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+/// 一つの単語の変更。追加なら`before`が、削除なら`after`が`None`。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub word_uuid: String,
+    pub before: Option<VvUserDictWord>,
+    pub after: Option<VvUserDictWord>,
+}
+
+/// 履歴の一件。一回の操作で変わった単語をまとめて持つ。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u64,
+    /// UNIX時刻（ミリ秒）。
+    pub timestamp: u128,
+    pub operation: String,
+    pub author: Option<String>,
+    /// 名前付きの辞書の変更なら、その名前。既定の辞書なら`None`。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_dict: Option<String>,
+    pub changes: Vec<Change>,
+}
+
+/// 最後に書いた履歴のID。初めて書くときにファイルから読む。
+static LAST_ID: Lazy<Mutex<Option<u64>>> = Lazy::new(|| Mutex::new(None));
+
+/// 変更前後の単語を比べる。
+pub fn diff(before: &HashMap<String, VvUserDictWord>, after: &HashMap<String, VvUserDictWord>) -> Vec<Change> {
+    let mut changes: Vec<Change> = before
+        .iter()
+        .filter(|(word_uuid, word)| after.get(*word_uuid) != Some(word))
+        .map(|(word_uuid, word)| Change {
+            word_uuid: word_uuid.clone(),
+            before: Some(word.clone()),
+            after: after.get(word_uuid).cloned(),
+        })
+        .chain(
+            after
+                .iter()
+                .filter(|(word_uuid, _)| !before.contains_key(*word_uuid))
+                .map(|(word_uuid, word)| Change {
+                    word_uuid: word_uuid.clone(),
+                    before: None,
+                    after: Some(word.clone()),
+                }),
+        )
+        .collect();
+    changes.sort_by(|a, b| a.word_uuid.cmp(&b.word_uuid));
+    changes
+}
+
+fn read(path: &Path) -> anyhow::Result<String> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 履歴を読む。書き込みの途中で落ちたときの壊れた最後の行は飛ばし、その位置（バイト）も返す。
+fn parse(text: &str) -> anyhow::Result<(Vec<Entry>, Option<usize>)> {
+    let mut entries = vec![];
+    let mut offset = 0;
+    let mut lines = text.split_inclusive('\n');
+    while let Some(line) = lines.next() {
+        let start = offset;
+        offset += line.len();
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) if lines.clone().all(|line| line.trim().is_empty()) => return Ok((entries, Some(start))),
+            Err(e) => bail!("履歴の{}バイト目からの行が壊れています：{}", start, e),
+        }
+    }
+    Ok((entries, None))
+}
+
+/// 古い順にすべての履歴を読む。
+pub fn entries(path: &Path) -> anyhow::Result<Vec<Entry>> {
+    let (entries, torn) = parse(&read(path)?)?;
+    if torn.is_some() {
+        warn!("Ignoring a torn last line in user dict history {:?}", path);
+    }
+    Ok(entries)
+}
+
+/// 壊れた最後の行を切り詰め、最後の履歴のIDを返す。初めて書く前に一度だけ呼ぶ。
+fn repair(path: &Path) -> anyhow::Result<u64> {
+    let (entries, torn) = parse(&read(path)?).context("履歴を読めないため、書き足せません。")?;
+    if let Some(torn) = torn {
+        warn!("Truncating a torn last line in user dict history {:?}", path);
+        fs::OpenOptions::new().write(true).open(path)?.set_len(torn as u64)?;
+    }
+    Ok(entries.iter().map(|entry| entry.id).max().unwrap_or(0))
+}
+
+/// 履歴を一件書き足す。変更が無ければ何もしない。
+pub fn append(
+    path: &Path,
+    operation: &str,
+    author: Option<String>,
+    user_dict: Option<String>,
+    changes: Vec<Change>,
+) -> anyhow::Result<Option<Entry>> {
+    if changes.is_empty() {
+        return Ok(None);
+    }
+    let mut last_id = LAST_ID.lock().unwrap();
+    let id = match *last_id {
+        Some(id) => id + 1,
+        None => repair(path)? + 1,
+    };
+    let entry = Entry {
+        id,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or(0),
+        operation: operation.to_string(),
+        author,
+        user_dict,
+        changes,
+    };
+
+    let line = format!("{}\n", serde_json::to_string(&entry)?);
+    let written = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| {
+            file.write_all(line.as_bytes())?;
+            file.sync_all()
+        });
+    // 途中まで書いたかもしれないので、次に書くときにもう一度切り詰める。
+    *last_id = written.is_ok().then_some(id);
+    written?;
+    Ok(Some(entry))
+}
+
+/// 変更を打ち消す。後から変更された単語のUUIDを返し、その単語はそのままにする。
+pub fn revert(words: &mut HashMap<String, VvUserDictWord>, changes: &[Change], force: bool) -> Vec<String> {
+    let mut conflicts = vec![];
+    for change in changes.iter().rev() {
+        if words.get(&change.word_uuid) != change.after.as_ref() {
+            conflicts.push(change.word_uuid.clone());
+            if !force {
+                continue;
+            }
+        }
+        match &change.before {
+            Some(word) => {
+                words.insert(change.word_uuid.clone(), word.clone());
+            }
+            None => {
+                words.remove(&change.word_uuid);
+            }
+        }
+    }
+    conflicts
+}
@@ -0,0 +1,249 @@
+use crate::models::{VvUserDictWord, WordType};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap},
+    sync::atomic::{self, AtomicU64},
+};
+
+/// ユーザー辞書の検索用の索引。辞書を変更したときは一緒に更新する。
+#[derive(Debug, Default)]
+pub struct DictIndex {
+    words: HashMap<String, VvUserDictWord>,
+    /// (表記, UUID)。前方一致検索に使う。
+    by_surface: BTreeSet<(String, String)>,
+    /// (読み, UUID)。
+    by_pronunciation: BTreeSet<(String, String)>,
+    version: u64,
+}
+
+/// 索引の版の通し番号。辞書を作り直しても同じ版にならないよう、すべての索引で共有する。
+static LAST_VERSION: AtomicU64 = AtomicU64::new(0);
+
+fn next_version() -> u64 {
+    LAST_VERSION.fetch_add(1, atomic::Ordering::Relaxed) + 1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    Surface,
+    Pronunciation,
+    /// 表記と読みのどちらか。
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    Prefix,
+    Substring,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    Surface,
+    Pronunciation,
+    Priority,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone)]
+pub struct Search {
+    pub query: Option<String>,
+    pub field: SearchField,
+    pub mode: MatchMode,
+    pub word_type: Option<WordType>,
+    pub min_priority: Option<u32>,
+    pub max_priority: Option<u32>,
+    pub sort: SortKey,
+    pub order: SortOrder,
+    pub cursor: Option<String>,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub word_uuid: String,
+    pub word: VvUserDictWord,
+}
+
+/// 検索結果の一ページ分。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchPage {
+    pub words: Vec<SearchHit>,
+    /// 条件に合う単語の総数。
+    pub total: usize,
+    /// 次のページを取るときに`cursor`として渡す。最後のページでは`None`。
+    pub next_cursor: Option<String>,
+}
+
+/// 並べ替えのキー。優先度は数値で、それ以外は文字列で比べる。
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Key {
+    Priority(u32),
+    Text(String),
+}
+
+/// ページの続きの位置。最後に返した単語のキーとUUID。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cursor {
+    key: Key,
+    word_uuid: String,
+}
+
+fn encode_cursor(cursor: &Cursor) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(serde_json::to_vec(cursor).unwrap())
+}
+
+fn decode_cursor(cursor: &str) -> anyhow::Result<Cursor> {
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(cursor)?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+fn key_of(word: &VvUserDictWord, sort: SortKey) -> Key {
+    match sort {
+        SortKey::Surface => Key::Text(word.surface().to_string()),
+        SortKey::Pronunciation => Key::Text(word.pronunciation().to_string()),
+        SortKey::Priority => Key::Priority(word.priority()),
+    }
+}
+
+/// `(表記, UUID)`の集合から前方一致するもののUUID。
+fn prefix_range<'a>(set: &'a BTreeSet<(String, String)>, prefix: &'a str) -> impl Iterator<Item = &'a String> + 'a {
+    set.range((prefix.to_string(), String::new())..)
+        .take_while(move |(text, _)| text.starts_with(prefix))
+        .map(|(_, word_uuid)| word_uuid)
+}
+
+impl DictIndex {
+    pub fn new(words: HashMap<String, VvUserDictWord>) -> Self {
+        let mut index = DictIndex::default();
+        for (word_uuid, word) in words {
+            index.insert(word_uuid, word);
+        }
+        index.version = next_version();
+        index
+    }
+
+    pub fn words(&self) -> &HashMap<String, VvUserDictWord> {
+        &self.words
+    }
+
+    /// 中身が変わるたびに変わる番号。
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// 追加と更新のどちらにも使う。
+    pub fn insert(&mut self, word_uuid: String, word: VvUserDictWord) {
+        self.remove(&word_uuid);
+        self.by_surface.insert((word.surface().to_string(), word_uuid.clone()));
+        self.by_pronunciation
+            .insert((word.pronunciation().to_string(), word_uuid.clone()));
+        self.words.insert(word_uuid, word);
+        self.version = next_version();
+    }
+
+    pub fn remove(&mut self, word_uuid: &str) {
+        if let Some(word) = self.words.remove(word_uuid) {
+            self.by_surface
+                .remove(&(word.surface().to_string(), word_uuid.to_string()));
+            self.by_pronunciation
+                .remove(&(word.pronunciation().to_string(), word_uuid.to_string()));
+            self.version = next_version();
+        }
+    }
+
+    fn matches_text(&self, word: &VvUserDictWord, search: &Search, query: &str) -> bool {
+        let matches = |text: &str| match search.mode {
+            MatchMode::Prefix => text.starts_with(query),
+            MatchMode::Substring => text.contains(query),
+        };
+        match search.field {
+            SearchField::Surface => matches(word.surface()),
+            SearchField::Pronunciation => matches(word.pronunciation()),
+            SearchField::Both => matches(word.surface()) || matches(word.pronunciation()),
+        }
+    }
+
+    pub fn search(&self, search: &Search) -> anyhow::Result<SearchPage> {
+        let cursor = search.cursor.as_deref().map(decode_cursor).transpose()?;
+        let query = search.query.as_deref().filter(|query| !query.is_empty());
+
+        // 前方一致なら索引で候補を絞る。
+        let candidates: Vec<&String> = match (query, search.mode, search.field) {
+            (Some(query), MatchMode::Prefix, SearchField::Surface) => prefix_range(&self.by_surface, query).collect(),
+            (Some(query), MatchMode::Prefix, SearchField::Pronunciation) => {
+                prefix_range(&self.by_pronunciation, query).collect()
+            }
+            (Some(query), MatchMode::Prefix, SearchField::Both) => {
+                let mut candidates: Vec<&String> = prefix_range(&self.by_surface, query)
+                    .chain(prefix_range(&self.by_pronunciation, query))
+                    .collect();
+                candidates.sort();
+                candidates.dedup();
+                candidates
+            }
+            _ => self.words.keys().collect(),
+        };
+
+        let mut hits: Vec<(Key, &String, &VvUserDictWord)> = candidates
+            .into_iter()
+            .map(|word_uuid| (word_uuid, &self.words[word_uuid]))
+            .filter(|(_, word)| query.is_none_or(|query| self.matches_text(word, search, query)))
+            .filter(|(_, word)| search.word_type.is_none_or(|word_type| word.word_type() == word_type))
+            .filter(|(_, word)| search.min_priority.is_none_or(|min| word.priority() >= min))
+            .filter(|(_, word)| search.max_priority.is_none_or(|max| word.priority() <= max))
+            .map(|(word_uuid, word)| (key_of(word, search.sort), word_uuid, word))
+            .collect();
+        let order = |a: (&Key, &String), b: (&Key, &String)| match search.order {
+            SortOrder::Asc => a.cmp(&b),
+            SortOrder::Desc => b.cmp(&a),
+        };
+        hits.sort_by(|a, b| order((&a.0, a.1), (&b.0, b.1)));
+        let total = hits.len();
+
+        let start = match &cursor {
+            Some(cursor) => hits
+                .iter()
+                .position(|(key, word_uuid, _)| {
+                    order((key, word_uuid), (&cursor.key, &cursor.word_uuid)) == Ordering::Greater
+                })
+                .unwrap_or(hits.len()),
+            None => 0,
+        };
+        let page: Vec<_> = hits.iter().skip(start).take(search.limit).collect();
+        let next_cursor =
+            (start + page.len() < hits.len())
+                .then(|| page.last())
+                .flatten()
+                .map(|(key, word_uuid, _)| {
+                    encode_cursor(&Cursor {
+                        key: key.clone(),
+                        word_uuid: word_uuid.to_string(),
+                    })
+                });
+
+        Ok(SearchPage {
+            words: page
+                .into_iter()
+                .map(|(_, word_uuid, word)| SearchHit {
+                    word_uuid: word_uuid.to_string(),
+                    word: (*word).clone(),
+                })
+                .collect(),
+            total,
+            next_cursor,
+        })
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, warn};
+use voicevox_core_rs::UserDict;
+
+/// 残しておくバックアップの数。
+pub const MAX_BACKUPS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    name: String,
+    /// UNIX時刻（ミリ秒）。
+    created_at: u128,
+}
+
+fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0)
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+/// `./user_dict.json`なら`./user_dict_backups`。
+pub fn backup_dir(path: &Path) -> PathBuf {
+    path.with_file_name(format!("{}_backups", file_stem(path)))
+}
+
+/// 新しい順に並べたバックアップ。
+pub fn backups(path: &Path) -> Vec<Backup> {
+    let prefix = format!("{}.", file_stem(path));
+    let Ok(entries) = fs::read_dir(backup_dir(path)) else {
+        return vec![];
+    };
+    let mut backups: Vec<Backup> = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            let created_at = name.strip_prefix(&prefix)?.strip_suffix(".json")?.parse().ok()?;
+            Some(Backup { name, created_at })
+        })
+        .collect();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    backups
+}
+
+/// 名前で指定したバックアップのパス。一覧にない名前はエラーにする。
+pub fn backup_path(path: &Path, name: &str) -> anyhow::Result<PathBuf> {
+    if !backups(path).iter().any(|backup| backup.name == name) {
+        bail!("バックアップが見つかりません：{}", name);
+    }
+    Ok(backup_dir(path).join(name))
+}
+
+/// 今のファイルをバックアップに回し、古いものを消す。
+fn rotate(path: &Path) -> anyhow::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir)?;
+    let mut created_at = now_millis();
+    // 同じミリ秒に保存が重なっても上書きしない。
+    while dir.join(format!("{}.{}.json", file_stem(path), created_at)).exists() {
+        created_at += 1;
+    }
+    fs::copy(path, dir.join(format!("{}.{}.json", file_stem(path), created_at)))?;
+    for backup in backups(path).into_iter().skip(MAX_BACKUPS) {
+        fs::remove_file(dir.join(&backup.name))?;
+    }
+    Ok(())
+}
+
+/// 一時ファイルに書いてfsyncしてから置き換えるので、途中で落ちても元のファイルは壊れない。
+pub fn save(user_dict: &UserDict, path: &Path) -> anyhow::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let temp_path = tempfile::Builder::new()
+        .prefix(&format!(".{}.", file_stem(path)))
+        .suffix(".json")
+        .tempfile_in(dir)?
+        .into_temp_path();
+    user_dict.save(&temp_path)?;
+    // Windowsでは読み込み専用のハンドルでfsyncできない。
+    fs::OpenOptions::new().write(true).open(&temp_path)?.sync_all()?;
+
+    rotate(path).context("ユーザー辞書のバックアップに失敗しました。")?;
+    temp_path.persist(path)?;
+    // Windowsではディレクトリを開けないので無視する。
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// ファイルを消す。消す前の中身はバックアップに残す。
+pub fn remove(path: &Path) -> anyhow::Result<()> {
+    rotate(path)?;
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+/// ファイルを読み込む。読めなければ壊れたファイルを退避し、読める一番新しいバックアップから戻す。
+pub fn load(path: &Path) -> anyhow::Result<UserDict> {
+    let user_dict = UserDict::new()?;
+    if !path.exists() {
+        return Ok(user_dict);
+    }
+    match user_dict.load(path) {
+        Ok(()) => return Ok(user_dict),
+        Err(e) => warn!("Failed to load user dict from {:?}: {}", path, e),
+    }
+
+    let quarantine = path.with_file_name(format!("{}.corrupt-{}.json", file_stem(path), now_millis()));
+    fs::rename(path, &quarantine)?;
+    warn!("Moved corrupt user dict to {:?}", quarantine);
+
+    for backup in backups(path) {
+        let backup_path = backup_dir(path).join(&backup.name);
+        let user_dict = UserDict::new()?;
+        if user_dict.load(&backup_path).is_err() {
+            warn!("Failed to load user dict backup {:?}", backup_path);
+            continue;
+        }
+        fs::copy(&backup_path, path)?;
+        info!("Restored user dict from {:?}", backup_path);
+        return Ok(user_dict);
+    }
+    warn!("No usable user dict backup found; starting with an empty user dict");
+    Ok(UserDict::new()?)
+}
@@ -0,0 +1,272 @@
+use crate::kana::to_katakana;
+use anyhow::{bail, Context};
+use regex::Regex;
+
+/// ユーザー辞書の候補。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub surface: String,
+    pub pronunciation: String,
+    pub accent_type: usize,
+    /// 今の読み。テキストと読みの対応が取れなかったときは`None`。
+    pub current_reading: Option<String>,
+}
+
+/// テキストを文字種で区切った一片。仮名の部分は読みが分かっている。
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    surface: String,
+    is_kana: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharKind {
+    Kana,
+    /// 漢字や英数字など、読みが分からないもの。
+    Other,
+    /// 句読点や空白。読まない。
+    Skip,
+}
+
+fn char_kind(c: char) -> CharKind {
+    match c {
+        'ぁ'..='ゖ' | 'ァ'..='ヺ' | 'ー' => CharKind::Kana,
+        c if c.is_whitespace() || c.is_ascii_punctuation() => CharKind::Skip,
+        '、' | '。' | '，' | '．' | '・' | '「' | '」' | '『' | '』' | '（' | '）' | '！' | '？' | '…' => {
+            CharKind::Skip
+        }
+        _ => CharKind::Other,
+    }
+}
+
+/// 読みの分からない片が仮名を挟まずに並ぶと、読みの境目が決まらないのでエラーにする。
+fn segments(text: &str) -> anyhow::Result<Vec<Segment>> {
+    let mut segments: Vec<Segment> = vec![];
+    let mut previous = CharKind::Skip;
+    for c in text.chars() {
+        let kind = char_kind(c);
+        if kind == CharKind::Skip {
+            previous = kind;
+            continue;
+        }
+        match segments.last_mut() {
+            Some(last) if kind == previous => last.surface.push(c),
+            Some(last) if kind == CharKind::Other && !last.is_kana => bail!(
+                "「{}」と「{}」の間に仮名が無いため、読みを分けられません。別々に指定してください。",
+                last.surface,
+                c
+            ),
+            _ => segments.push(Segment {
+                surface: c.to_string(),
+                is_kana: kind == CharKind::Kana,
+            }),
+        }
+        previous = kind;
+    }
+    Ok(segments)
+}
+
+fn vowel_of(c: char) -> Option<char> {
+    let rows = [
+        ("アカサタナハマヤラワガザダバパァャヮヴ", 'ア'),
+        ("イキシチニヒミリギジヂビピィ", 'イ'),
+        ("ウクスツヌフムユルグズヅブプゥュ", 'ウ'),
+        ("エケセテネヘメレゲゼデベペェ", 'エ'),
+        ("オコソトノホモヨロヲゴゾドボポォョ", 'オ'),
+    ];
+    rows.iter().find(|(row, _)| row.contains(c)).map(|(_, vowel)| *vowel)
+}
+
+/// 長音の書き方の揺れを吸収する。文字数は変えない。
+fn fold(reading: &str) -> String {
+    let mut folded = String::new();
+    let mut previous_vowel = None;
+    for c in reading.chars() {
+        let c = match (c, previous_vowel) {
+            ('ー', Some(vowel)) => vowel,
+            ('ウ', Some('オ')) => 'オ',
+            ('イ', Some('エ')) => 'エ',
+            ('ヲ', _) => 'オ',
+            ('ヅ', _) => 'ズ',
+            ('ヂ', _) => 'ジ',
+            (c, _) => c,
+        };
+        previous_vowel = vowel_of(c);
+        folded.push(c);
+    }
+    folded
+}
+
+/// 仮名の部分を手がかりに、読み全体を各片の読みに割り当てる正規表現。
+fn alignment_regex(segments: &[Segment]) -> anyhow::Result<Regex> {
+    let mut pattern = String::from("^");
+    for segment in segments {
+        if !segment.is_kana {
+            pattern.push_str("(.+?)");
+            continue;
+        }
+        pattern.push('(');
+        for c in fold(&to_katakana(&segment.surface)).chars() {
+            // 助詞として読まれるかもしれないもの。
+            match c {
+                'ハ' => pattern.push_str("[ハワ]"),
+                'ヘ' => pattern.push_str("[ヘエ]"),
+                c => pattern.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        pattern.push(')');
+    }
+    pattern.push('$');
+    Regex::new(&pattern).context("テキストが長すぎます。")
+}
+
+/// 各片の読みを、読みの中の文字位置の範囲で返す。
+fn align(regex: &Regex, reading: &str) -> Option<Vec<(usize, usize)>> {
+    let folded = fold(reading);
+    let captures = regex.captures(&folded)?;
+    Some(
+        captures
+            .iter()
+            .skip(1)
+            .map(|group| {
+                let group = group.unwrap();
+                (
+                    folded[..group.start()].chars().count(),
+                    folded[..group.end()].chars().count(),
+                )
+            })
+            .collect(),
+    )
+}
+
+fn slice(chars: &[char], (start, end): (usize, usize)) -> String {
+    chars[start..end].iter().collect()
+}
+
+/// `mora_count`モーラの単語のアクセント型を推測する。短い語は頭高、それ以外は平板にする。
+pub fn guess_accent_type(mora_count: usize) -> usize {
+    if mora_count <= 2 {
+        1
+    } else {
+        0
+    }
+}
+
+/// テキストと意図した読みから、読み間違えている部分のユーザー辞書の候補を作る。
+///
+/// `intended`はカタカナ（ひらがなも可）で、アクセント核の直後に`'`を置くとアクセント型として使う。
+/// `current`は今の読み。`count_moras`はカタカナのモーラ数を数える関数。
+pub fn suggest(
+    text: &str,
+    intended: &str,
+    current: &str,
+    count_moras: impl Fn(&str) -> usize,
+) -> anyhow::Result<Vec<Suggestion>> {
+    let intended = to_katakana(&intended.chars().filter(|c| !c.is_whitespace()).collect::<String>());
+    let mut accent_marks = vec![];
+    let mut intended_chars = vec![];
+    for c in intended.chars() {
+        if c == '\'' {
+            accent_marks.push(intended_chars.len());
+        } else if ('ァ'..='ヺ').contains(&c) || c == 'ー' {
+            intended_chars.push(c);
+        } else {
+            bail!("読みはカタカナで指定してください：{}", c);
+        }
+    }
+    let intended: String = intended_chars.iter().collect();
+
+    let segments = segments(text)?;
+    if segments.is_empty() {
+        bail!("テキストが空です。");
+    }
+    let regex = alignment_regex(&segments)?;
+    let Some(intended_ranges) = align(&regex, &intended) else {
+        bail!("テキストの仮名の部分と読みが対応しません。");
+    };
+    let current_chars: Vec<char> = current.chars().collect();
+    let current_ranges = align(&regex, current);
+
+    let mut suggestions = vec![];
+    for (i, segment) in segments.iter().enumerate() {
+        if segment.is_kana {
+            continue;
+        }
+        let pronunciation = slice(&intended_chars, intended_ranges[i]);
+        let current_reading = current_ranges.as_ref().map(|ranges| slice(&current_chars, ranges[i]));
+        if current_reading
+            .as_ref()
+            .is_some_and(|reading| fold(reading) == fold(&pronunciation))
+        {
+            continue;
+        }
+
+        let (start, end) = intended_ranges[i];
+        let accent_type = accent_marks
+            .iter()
+            .find(|mark| start < **mark && **mark <= end)
+            .map(|mark| count_moras(&slice(&intended_chars, (start, *mark))))
+            .unwrap_or_else(|| guess_accent_type(count_moras(&pronunciation)));
+        suggestions.push(Suggestion {
+            surface: segment.surface.clone(),
+            pronunciation,
+            accent_type,
+            current_reading,
+        });
+    }
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kana::count_moras;
+
+    #[test]
+    fn adjacent_runs_are_rejected() {
+        assert!(suggest("東京、大阪", "トーキョーオーサカ", "トーキョーオーサカ", count_moras).is_err());
+        assert!(suggest("東京 大阪", "トーキョーオーサカ", "トーキョーオーサカ", count_moras).is_err());
+    }
+
+    #[test]
+    fn particle_ha_read_as_wa() {
+        let suggestions = suggest("今日は晴れ", "キョーワハレ", "コンニチワハレ", count_moras).unwrap();
+        assert_eq!(
+            suggestions,
+            vec![Suggestion {
+                surface: "今日".to_string(),
+                pronunciation: "キョー".to_string(),
+                accent_type: 1,
+                current_reading: Some("コンニチ".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn particle_he_read_as_e() {
+        let suggestions = suggest("日本へ行く", "ニッポンエイク", "ニホンエイク", count_moras).unwrap();
+        assert_eq!(
+            suggestions,
+            vec![Suggestion {
+                surface: "日本".to_string(),
+                pronunciation: "ニッポン".to_string(),
+                accent_type: 0,
+                current_reading: Some("ニホン".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn accent_mark() {
+        let suggestions = suggest("日本へ行く", "ニッポ'ンエイク", "ニホンエイク", count_moras).unwrap();
+        assert_eq!(suggestions[0].pronunciation, "ニッポン");
+        assert_eq!(suggestions[0].accent_type, 3);
+    }
+
+    #[test]
+    fn long_text_is_an_error() {
+        let text = "漢あ".repeat(100_000);
+        let error = suggest(&text, "ア", "ア", count_moras).unwrap_err();
+        assert_eq!(error.to_string(), "テキストが長すぎます。");
+    }
+}
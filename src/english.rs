@@ -0,0 +1,569 @@
+use crate::{
+    normalize::{spell_out, LETTER_READINGS},
+    utils::to_full_width,
+};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::{HashMap, HashSet};
+use tracing::warn;
+
+pub static ENGLISH_DICT_PATH: &str = "./english_dict.json";
+
+/// よく使う英単語の読み。規則では読めないものを中心にしている。
+static BUILTIN_DICT: &[(&str, &str)] = &[
+    ("a", "ア"),
+    ("about", "アバウト"),
+    ("access", "アクセス"),
+    ("account", "アカウント"),
+    ("action", "アクション"),
+    ("and", "アンド"),
+    ("anime", "アニメ"),
+    ("app", "アップ"),
+    ("apple", "アップル"),
+    ("are", "アー"),
+    ("audio", "オーディオ"),
+    ("base", "ベース"),
+    ("book", "ブック"),
+    ("build", "ビルド"),
+    ("business", "ビジネス"),
+    ("by", "バイ"),
+    ("case", "ケース"),
+    ("cloud", "クラウド"),
+    ("code", "コード"),
+    ("come", "カム"),
+    ("computer", "コンピューター"),
+    ("data", "データ"),
+    ("design", "デザイン"),
+    ("do", "ドゥー"),
+    ("engine", "エンジン"),
+    ("error", "エラー"),
+    ("file", "ファイル"),
+    ("for", "フォー"),
+    ("from", "フロム"),
+    ("fun", "ファン"),
+    ("game", "ゲーム"),
+    ("get", "ゲット"),
+    ("give", "ギブ"),
+    ("good", "グッド"),
+    ("google", "グーグル"),
+    ("have", "ハブ"),
+    ("hello", "ハロー"),
+    ("home", "ホーム"),
+    ("how", "ハウ"),
+    ("i", "アイ"),
+    ("image", "イメージ"),
+    ("in", "イン"),
+    ("is", "イズ"),
+    ("it", "イット"),
+    ("java", "ジャバ"),
+    ("karate", "カラテ"),
+    ("key", "キー"),
+    ("know", "ノウ"),
+    ("like", "ライク"),
+    ("line", "ライン"),
+    ("live", "ライブ"),
+    ("love", "ラブ"),
+    ("machine", "マシン"),
+    ("make", "メイク"),
+    ("model", "モデル"),
+    ("mode", "モード"),
+    ("music", "ミュージック"),
+    ("my", "マイ"),
+    ("name", "ネーム"),
+    ("new", "ニュー"),
+    ("news", "ニュース"),
+    ("node", "ノード"),
+    ("note", "ノート"),
+    ("now", "ナウ"),
+    ("of", "オブ"),
+    ("office", "オフィス"),
+    ("on", "オン"),
+    ("one", "ワン"),
+    ("online", "オンライン"),
+    ("open", "オープン"),
+    ("people", "ピープル"),
+    ("phone", "フォン"),
+    ("play", "プレイ"),
+    ("please", "プリーズ"),
+    ("python", "パイソン"),
+    ("rust", "ラスト"),
+    ("sake", "サケ"),
+    ("server", "サーバー"),
+    ("service", "サービス"),
+    ("site", "サイト"),
+    ("software", "ソフトウェア"),
+    ("some", "サム"),
+    ("system", "システム"),
+    ("take", "テイク"),
+    ("test", "テスト"),
+    ("text", "テキスト"),
+    ("the", "ザ"),
+    ("time", "タイム"),
+    ("to", "トゥー"),
+    ("type", "タイプ"),
+    ("update", "アップデート"),
+    ("use", "ユーズ"),
+    ("user", "ユーザー"),
+    ("video", "ビデオ"),
+    ("voice", "ボイス"),
+    ("web", "ウェブ"),
+    ("what", "ワット"),
+    ("window", "ウィンドウ"),
+    ("windows", "ウィンドウズ"),
+    ("with", "ウィズ"),
+    ("word", "ワード"),
+    ("world", "ワールド"),
+    ("you", "ユー"),
+    ("your", "ユア"),
+];
+
+/// 組み込みの辞書に`ENGLISH_DICT_PATH`の内容を上書きしたもの。
+static DICT: Lazy<HashMap<String, String>> = Lazy::new(|| {
+    let mut dict: HashMap<String, String> = BUILTIN_DICT
+        .iter()
+        .map(|(word, reading)| (word.to_string(), reading.to_string()))
+        .collect();
+    if let Ok(json) = std::fs::read_to_string(ENGLISH_DICT_PATH) {
+        match serde_json::from_str::<HashMap<String, String>>(&json) {
+            Ok(words) => dict.extend(words.into_iter().map(|(word, reading)| (word.to_lowercase(), reading))),
+            Err(e) => warn!("Failed to parse english dict from {:?}: {}", ENGLISH_DICT_PATH, e),
+        }
+    }
+    dict
+});
+
+static TOKEN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[A-Za-z][A-Za-z0-9_']*").unwrap());
+
+/// 子音ごとのア段〜オ段のカナ。英語とローマ字の両方で使う。
+static KANA_TABLE: &[(&str, [&str; 5])] = &[
+    ("", ["ア", "イ", "ウ", "エ", "オ"]),
+    ("k", ["カ", "キ", "ク", "ケ", "コ"]),
+    ("g", ["ガ", "ギ", "グ", "ゲ", "ゴ"]),
+    ("s", ["サ", "シ", "ス", "セ", "ソ"]),
+    ("z", ["ザ", "ジ", "ズ", "ゼ", "ゾ"]),
+    ("t", ["タ", "ティ", "トゥ", "テ", "ト"]),
+    ("d", ["ダ", "ディ", "ドゥ", "デ", "ド"]),
+    ("n", ["ナ", "ニ", "ヌ", "ネ", "ノ"]),
+    ("h", ["ハ", "ヒ", "フ", "ヘ", "ホ"]),
+    ("b", ["バ", "ビ", "ブ", "ベ", "ボ"]),
+    ("p", ["パ", "ピ", "プ", "ペ", "ポ"]),
+    ("m", ["マ", "ミ", "ム", "メ", "モ"]),
+    ("y", ["ヤ", "イ", "ユ", "イェ", "ヨ"]),
+    ("r", ["ラ", "リ", "ル", "レ", "ロ"]),
+    ("w", ["ワ", "ウィ", "ウ", "ウェ", "ウォ"]),
+    ("f", ["ファ", "フィ", "フ", "フェ", "フォ"]),
+    ("v", ["バ", "ビ", "ブ", "ベ", "ボ"]),
+    ("ch", ["チャ", "チ", "チュ", "チェ", "チョ"]),
+    ("sh", ["シャ", "シ", "シュ", "シェ", "ショ"]),
+    ("j", ["ジャ", "ジ", "ジュ", "ジェ", "ジョ"]),
+    ("ts", ["ツァ", "ツィ", "ツ", "ツェ", "ツォ"]),
+    ("kw", ["クア", "クイ", "ク", "クエ", "クオ"]),
+    ("ky", ["キャ", "キ", "キュ", "キェ", "キョ"]),
+    ("gy", ["ギャ", "ギ", "ギュ", "ギェ", "ギョ"]),
+    ("ny", ["ニャ", "ニ", "ニュ", "ニェ", "ニョ"]),
+    ("hy", ["ヒャ", "ヒ", "ヒュ", "ヒェ", "ヒョ"]),
+    ("by", ["ビャ", "ビ", "ビュ", "ビェ", "ビョ"]),
+    ("py", ["ピャ", "ピ", "ピュ", "ピェ", "ピョ"]),
+    ("my", ["ミャ", "ミ", "ミュ", "ミェ", "ミョ"]),
+    ("ry", ["リャ", "リ", "リュ", "リェ", "リョ"]),
+];
+
+fn kana(consonant: &str, vowel: char) -> Option<&'static str> {
+    let index = "aiueo".find(vowel)?;
+    KANA_TABLE
+        .iter()
+        .find(|(c, _)| *c == consonant)
+        .map(|(_, row)| row[index])
+}
+
+fn is_vowel(c: char) -> bool {
+    "aeiou".contains(c)
+}
+
+/// ローマ字には無い英語らしい綴りを含むか。rate、hope、teamなど。
+fn looks_english(word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+    // 子音+母音+子音+eで終わるものは語末のeを読まない英語の綴りとみなす。karaokeは除く。
+    let silent_e = chars.len() >= 4
+        && chars.ends_with(&['e'])
+        && !is_vowel(chars[chars.len() - 2])
+        && is_vowel(chars[chars.len() - 3])
+        && !is_vowel(chars[chars.len() - 4]);
+    silent_e || word.contains("ea") || word.contains("ee")
+}
+
+/// ローマ字として読めればカタカナにする。
+fn romaji_to_katakana(word: &str) -> Option<String> {
+    // ヘボン式と訓令式の揺れを吸収する。
+    let aliases = [("shi", "si"), ("chi", "ti"), ("tsu", "tu"), ("fu", "hu"), ("ji", "zi")];
+    let chars: Vec<char> = word.chars().collect();
+    let mut result = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == 'n' && next.is_none_or(|next| !is_vowel(next) && next != 'y') {
+            result.push('ン');
+            i += 1 + usize::from(next == Some('n') && chars.get(i + 2).is_none_or(|c| !is_vowel(*c)));
+            continue;
+        }
+        if c == 'm' && matches!(next, Some('b' | 'p' | 'm')) {
+            result.push('ン');
+            i += 1;
+            continue;
+        }
+        if !is_vowel(c) && (next == Some(c) || (c == 't' && next == Some('c'))) {
+            result.push('ッ');
+            i += 1;
+            continue;
+        }
+        let rest: String = chars[i..].iter().collect();
+        let mut matched = None;
+        for length in (1..=4).rev() {
+            let Some(syllable) = rest.get(..length) else { continue };
+            let syllable = aliases
+                .iter()
+                .find(|(alias, _)| *alias == syllable)
+                .map_or(syllable, |(_, canonical)| canonical);
+            let (consonant, vowel) = syllable.split_at(syllable.len() - 1);
+            let consonant = match consonant {
+                "sy" => "sh",
+                "zy" | "jy" => "j",
+                "ty" | "cy" => "ch",
+                c => c,
+            };
+            let kana = match (consonant, syllable) {
+                (_, "ti") => Some("チ"),
+                (_, "tu") => Some("ツ"),
+                (_, "hu") => Some("フ"),
+                (_, "zi") => Some("ジ"),
+                (_, "wo") => Some("ヲ"),
+                ("l" | "q" | "x" | "c" | "v" | "kw", _) => None,
+                _ => kana(consonant, vowel.chars().next().unwrap()),
+            };
+            if let Some(kana) = kana {
+                matched = Some((length, kana));
+                break;
+            }
+        }
+        let (length, kana) = matched?;
+        result.push_str(kana);
+        i += length;
+    }
+    Some(result)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Consonant(&'static str),
+    /// 子音と合わせる母音と、その後に続けるカナ。母音が`y`なら拗音（ユ）にする。
+    Vowel(char, &'static str),
+    Sokuon,
+}
+
+fn english_units(word: &str) -> Vec<Unit> {
+    let chars: Vec<char> = word.chars().collect();
+    let len = chars.len();
+    let at = |i: usize| chars.get(i).copied();
+    let is_vowel_at = |i: usize| at(i).is_some_and(|c| is_vowel(c) || c == 'y');
+
+    // 語末の発音しないe（make, time, note など）の直前の母音を長母音にする。
+    let magic_e = (len >= 3
+        && chars[len - 1] == 'e'
+        && !is_vowel_at(len - 2)
+        && is_vowel(chars[len - 3])
+        && (len == 3 || !is_vowel_at(len - 4)))
+    .then(|| len - 3);
+    // 子音の後の語末のeは読まない（bridge, little など）。
+    let silent_e = len > 3 && chars[len - 1] == 'e' && !is_vowel_at(len - 2);
+    let end = if magic_e.is_some() || silent_e { len - 1 } else { len };
+
+    let mut units = vec![];
+    let mut i = 0;
+    while i < end {
+        let c = chars[i];
+        let rest: String = chars[i..end].iter().collect();
+        let followed_by_consonant = |offset: usize| i + offset >= end || !is_vowel_at(i + offset);
+
+        if Some(i) == magic_e {
+            units.push(match c {
+                'a' => Unit::Vowel('e', "イ"),
+                'i' => Unit::Vowel('a', "イ"),
+                'o' => Unit::Vowel('o', "ー"),
+                'u' => Unit::Vowel('y', "ー"),
+                _ => Unit::Vowel('i', "ー"),
+            });
+            i += 1;
+            continue;
+        }
+
+        let vowel = [
+            ("igh", Unit::Vowel('a', "イ")),
+            ("eau", Unit::Vowel('o', "ー")),
+            ("ee", Unit::Vowel('i', "ー")),
+            ("ea", Unit::Vowel('i', "ー")),
+            ("oo", Unit::Vowel('u', "ー")),
+            ("ou", Unit::Vowel('a', "ウ")),
+            ("ai", Unit::Vowel('e', "イ")),
+            ("ay", Unit::Vowel('e', "イ")),
+            ("oa", Unit::Vowel('o', "ー")),
+            ("oi", Unit::Vowel('o', "イ")),
+            ("oy", Unit::Vowel('o', "イ")),
+            ("au", Unit::Vowel('o', "ー")),
+            ("aw", Unit::Vowel('o', "ー")),
+            ("ue", Unit::Vowel('y', "ー")),
+            ("ew", Unit::Vowel('y', "ー")),
+        ]
+        .into_iter()
+        .find(|(pattern, _)| rest.starts_with(pattern));
+        if let Some((pattern, unit)) = vowel {
+            units.push(unit);
+            i += pattern.len();
+            continue;
+        }
+        if rest.starts_with("ow") && i + 2 == end {
+            units.push(Unit::Vowel('o', "ー"));
+            i += 2;
+            continue;
+        }
+        if rest.starts_with("ow") {
+            units.push(Unit::Vowel('a', "ウ"));
+            i += 2;
+            continue;
+        }
+        // 母音+rは長音にする（car, her, for など）。
+        if is_vowel(c) && at(i + 1) == Some('r') && followed_by_consonant(2) {
+            units.push(match c {
+                'a' => Unit::Vowel('a', "ー"),
+                'o' => Unit::Vowel('o', "ー"),
+                _ => Unit::Vowel('a', "ー"),
+            });
+            i += 2;
+            continue;
+        }
+        if is_vowel(c) {
+            units.push(match c {
+                'u' if i + 2 < end && !is_vowel_at(i + 1) && is_vowel_at(i + 2) => Unit::Vowel('y', "ー"),
+                'u' => Unit::Vowel('a', ""),
+                _ => Unit::Vowel(c, ""),
+            });
+            i += 1;
+            continue;
+        }
+        if c == 'y' && i > 0 {
+            units.push(if i + 1 == end {
+                Unit::Vowel('i', "ー")
+            } else if is_vowel_at(i + 1) {
+                Unit::Consonant("y")
+            } else {
+                Unit::Vowel('i', "")
+            });
+            i += 1;
+            continue;
+        }
+
+        let after_vowel = units.last().is_some_and(|unit| matches!(unit, Unit::Vowel(..)));
+        let consonant = [
+            ("tch", &[Unit::Sokuon, Unit::Consonant("ch")][..]),
+            ("ck", &[Unit::Sokuon, Unit::Consonant("k")][..]),
+            ("dg", &[Unit::Consonant("j")][..]),
+            ("chr", &[Unit::Consonant("k"), Unit::Consonant("r")][..]),
+            ("ch", &[Unit::Consonant("ch")][..]),
+            ("sh", &[Unit::Consonant("sh")][..]),
+            ("ph", &[Unit::Consonant("f")][..]),
+            ("th", &[Unit::Consonant("s")][..]),
+            ("wh", &[Unit::Consonant("w")][..]),
+            ("qu", &[Unit::Consonant("kw")][..]),
+            ("ng", &[Unit::Consonant("n"), Unit::Consonant("g")][..]),
+            ("gh", &[][..]),
+            ("x", &[Unit::Consonant("k"), Unit::Consonant("s")][..]),
+        ]
+        .into_iter()
+        .find(|(pattern, _)| rest.starts_with(pattern));
+        if let Some((pattern, consonant_units)) = consonant {
+            let consonant_units = if !after_vowel && consonant_units.first() == Some(&Unit::Sokuon) {
+                &consonant_units[1..]
+            } else {
+                consonant_units
+            };
+            units.extend(consonant_units);
+            i += pattern.len();
+            continue;
+        }
+        if i == 0 && (rest.starts_with("kn") || rest.starts_with("wr")) {
+            i += 1;
+            continue;
+        }
+        if at(i + 1) == Some(c) {
+            if after_vowel && "pkdgbct".contains(c) {
+                units.push(Unit::Sokuon);
+            }
+            i += 1;
+            continue;
+        }
+        let soft = at(i + 1).is_some_and(|next| "eiy".contains(next));
+        // scienceのように、sの後の柔らかいcは読まない。
+        if c == 'c' && soft && i > 0 && chars[i - 1] == 's' {
+            i += 1;
+            continue;
+        }
+        units.push(Unit::Consonant(match c {
+            'c' if soft => "s",
+            'c' => "k",
+            'g' if at(i + 1).is_some_and(|next| next == 'e' || next == 'y') => "j",
+            'l' => "r",
+            'b' => "b",
+            'd' => "d",
+            'f' => "f",
+            'h' => "h",
+            'j' => "j",
+            'k' => "k",
+            'm' => "m",
+            'n' => "n",
+            'p' => "p",
+            'r' => "r",
+            's' => "s",
+            't' => "t",
+            'v' => "v",
+            'w' => "w",
+            'y' => "y",
+            'z' => "z",
+            _ => "g",
+        }));
+        i += 1;
+    }
+    units
+}
+
+/// 綴りの規則からおおよその読みを作る。
+fn english_to_katakana(word: &str) -> String {
+    let units = english_units(word);
+    let mut result = String::new();
+    let mut pending: Option<&str> = None;
+    let mut last_was_vowel = false;
+    for (index, unit) in units.iter().enumerate() {
+        match *unit {
+            Unit::Vowel(vowel, suffix) => {
+                let consonant = pending.take().unwrap_or("");
+                if vowel == 'y' {
+                    let youon = format!("{}y", consonant);
+                    match kana(&youon, 'u') {
+                        Some(kana) => result.push_str(kana),
+                        None => {
+                            result.push_str(kana(consonant, 'u').unwrap_or("ユ"));
+                            if !consonant.is_empty() {
+                                result.push('ュ');
+                            }
+                        }
+                    }
+                } else {
+                    result.push_str(kana(consonant, vowel).unwrap_or(""));
+                }
+                result.push_str(suffix);
+                last_was_vowel = true;
+            }
+            Unit::Consonant(consonant) => {
+                if let Some(previous) = pending.replace(consonant) {
+                    let next = Some(consonant);
+                    result.push_str(bare_consonant(previous, next, last_was_vowel, &result));
+                    last_was_vowel = false;
+                }
+            }
+            Unit::Sokuon => {
+                if let Some(previous) = pending.take() {
+                    result.push_str(bare_consonant(previous, None, last_was_vowel, &result));
+                }
+                result.push('ッ');
+                last_was_vowel = false;
+            }
+        }
+        if index + 1 == units.len() {
+            if let Some(previous) = pending.take() {
+                result.push_str(bare_consonant(previous, None, last_was_vowel, &result));
+            }
+        }
+    }
+    result
+}
+
+/// 母音が続かない子音のカナ。
+fn bare_consonant(consonant: &str, next: Option<&str>, after_vowel: bool, result: &str) -> &'static str {
+    match consonant {
+        "n" => "ン",
+        "m" if matches!(next, Some("b" | "p")) => "ン",
+        "r" if after_vowel && !result.ends_with('ー') => "ー",
+        "r" if after_vowel => "",
+        "h" => "",
+        "t" => "ト",
+        "d" => "ド",
+        "ch" => "チ",
+        "j" => "ジ",
+        "sh" => "シュ",
+        "w" => "ウ",
+        "y" => "イ",
+        "kw" => "ク",
+        consonant => kana(consonant, 'u').unwrap_or(""),
+    }
+}
+
+/// 識別子をcamelCaseやsnake_caseの区切りで分ける。
+fn split_identifier(token: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = token.char_indices().collect();
+    let mut parts = vec![];
+    let mut start = 0;
+    for (i, &(index, c)) in chars.iter().enumerate() {
+        if c == '_' || c == '\'' {
+            parts.push(&token[start..index]);
+            start = index + c.len_utf8();
+            continue;
+        }
+        let Some(&(_, previous)) = i.checked_sub(1).and_then(|i| chars.get(i)) else {
+            continue;
+        };
+        let next = chars.get(i + 1).map(|(_, next)| *next);
+        let boundary = (previous.is_ascii_lowercase() && c.is_ascii_uppercase())
+            || (previous.is_ascii_uppercase()
+                && c.is_ascii_uppercase()
+                && next.is_some_and(|n| n.is_ascii_lowercase()))
+            || (previous.is_ascii_digit() != c.is_ascii_digit() && previous != '_' && previous != '\'');
+        if boundary && start < index {
+            parts.push(&token[start..index]);
+            start = index;
+        }
+    }
+    parts.push(&token[start..]);
+    parts.into_iter().filter(|part| !part.is_empty()).collect()
+}
+
+fn read_word(word: &str) -> String {
+    if word.chars().all(|c| c.is_ascii_digit()) {
+        return word.to_string();
+    }
+    let lower = word.to_lowercase();
+    if let Some(reading) = DICT.get(&lower) {
+        return reading.clone();
+    }
+    if word.len() == 1 {
+        return LETTER_READINGS[(lower.as_bytes()[0] - b'a') as usize].to_string();
+    }
+    if word.chars().all(|c| c.is_ascii_uppercase()) || !lower.contains(['a', 'e', 'i', 'o', 'u', 'y']) {
+        return spell_out(word);
+    }
+    if looks_english(&lower) {
+        return english_to_katakana(&lower);
+    }
+    romaji_to_katakana(&lower).unwrap_or_else(|| english_to_katakana(&lower))
+}
+
+/// ラテン文字の語をカタカナにする。`protected`に含まれる語（ユーザー辞書の表記）はそのまま残す。
+pub fn transliterate(text: &str, protected: &HashSet<String>) -> String {
+    TOKEN_REGEX
+        .replace_all(text, |caps: &Captures| {
+            let token = &caps[0];
+            if protected.contains(token) || protected.contains(&to_full_width(token)) {
+                return token.to_string();
+            }
+            split_identifier(token).into_iter().map(read_word).collect()
+        })
+        .into_owned()
+}
@@ -0,0 +1,342 @@
+use crate::models::{AudioQuery, Mora};
+use anyhow::bail;
+
+/// 解析のフレーム周期（秒）。
+pub static FRAME_PERIOD: f32 = 0.01;
+/// F0推定はこのくらいのサンプリングレートまで間引いてから行う。これより低い参照音声は受け付けない。
+pub static ANALYSIS_RATE: u32 = 8000;
+/// 参照音声の長さの上限（秒）。位置合わせはモーラ数×フレーム数に比例して重くなる。
+pub static MAX_REFERENCE_DURATION: f32 = 30.0;
+/// 参照音声に合わせられるモーラ数の上限。
+pub static MAX_MORA_COUNT: usize = 200;
+static F0_FLOOR: f32 = 70.0;
+static F0_CEIL: f32 = 800.0;
+static YIN_THRESHOLD: f32 = 0.15;
+/// 最大音量に対してこれより小さいフレームを無音とみなす。
+static SILENCE_THRESHOLD: f32 = 0.05;
+/// 予測した長さからのずれに対するペナルティの重み。
+static DURATION_WEIGHT: f32 = 2.0;
+
+static UNVOICED_CONSONANTS: [&str; 13] = ["k", "s", "t", "ch", "ts", "h", "f", "p", "sh", "hy", "ky", "py", "ty"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    pub f0: Option<f32>,
+    /// 周期性の強さ。0〜1。
+    pub voicing: f32,
+    /// 最大のフレームを1としたRMS。
+    pub energy: f32,
+}
+
+/// 音声をフレームに切り、YINでF0を推定する。
+pub fn analyze(samples: &[f32], sample_rate: u32) -> anyhow::Result<Vec<Frame>> {
+    if sample_rate < ANALYSIS_RATE {
+        bail!(
+            "参照音声のサンプリングレートが低すぎます（{}Hz、下限{}Hz）。",
+            sample_rate,
+            ANALYSIS_RATE
+        );
+    }
+    let factor = (sample_rate / ANALYSIS_RATE).max(1) as usize;
+    let rate = sample_rate as f32 / factor as f32;
+    let signal: Vec<f32> = samples
+        .chunks(factor)
+        .map(|chunk| chunk.iter().sum::<f32>() / chunk.len() as f32)
+        .collect();
+
+    let hop = (rate * FRAME_PERIOD).round() as usize;
+    let tau_min = (rate / F0_CEIL).floor() as usize;
+    let tau_max = (rate / F0_FLOOR).ceil() as usize;
+    let window = tau_max * 2;
+    let frame_count = signal.len() / hop;
+
+    let mut frames: Vec<Frame> = (0..frame_count)
+        .map(|i| {
+            let center = i * hop;
+            let start = center.saturating_sub(window / 2);
+            let frame = &signal[start..(start + window + tau_max).min(signal.len())];
+            let energy = (frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32).sqrt();
+            let (f0, voicing) = yin(frame, window, tau_min, tau_max, rate);
+            Frame { f0, voicing, energy }
+        })
+        .collect();
+
+    let max_energy = frames.iter().map(|frame| frame.energy).fold(0.0, f32::max);
+    if max_energy > 0.0 {
+        for frame in &mut frames {
+            frame.energy /= max_energy;
+        }
+    }
+    Ok(frames)
+}
+
+fn yin(frame: &[f32], window: usize, tau_min: usize, tau_max: usize, rate: f32) -> (Option<f32>, f32) {
+    if frame.len() < window + tau_max {
+        return (None, 0.0);
+    }
+    let mut difference = vec![0.0; tau_max + 1];
+    for (tau, d) in difference.iter_mut().enumerate().skip(1) {
+        *d = (0..window).map(|j| (frame[j] - frame[j + tau]).powi(2)).sum();
+    }
+    // 累積平均で正規化する。
+    let mut normalized = vec![1.0; tau_max + 1];
+    let mut running_sum = 0.0;
+    for tau in 1..=tau_max {
+        running_sum += difference[tau];
+        normalized[tau] = if running_sum > 0.0 {
+            difference[tau] * tau as f32 / running_sum
+        } else {
+            1.0
+        };
+    }
+
+    let mut tau = tau_min.max(2);
+    while tau < tau_max {
+        if normalized[tau] < YIN_THRESHOLD {
+            while tau + 1 < tau_max && normalized[tau + 1] < normalized[tau] {
+                tau += 1;
+            }
+            // 放物線補間で周期を細かく求める。
+            let (a, b, c) = (normalized[tau - 1], normalized[tau], normalized[tau + 1]);
+            let shift = if a + c - 2.0 * b != 0.0 {
+                (a - c) / (2.0 * (a + c - 2.0 * b))
+            } else {
+                0.0
+            };
+            return (Some(rate / (tau as f32 + shift)), (1.0 - b).clamp(0.0, 1.0));
+        }
+        tau += 1;
+    }
+    let minimum = normalized[tau_min.max(1)..].iter().copied().fold(1.0, f32::min);
+    (None, (1.0 - minimum).clamp(0.0, 1.0) * 0.5)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PhonemeClass {
+    Silence,
+    Voiced,
+    Unvoiced,
+}
+
+impl PhonemeClass {
+    fn of_vowel(vowel: &str) -> Self {
+        match vowel {
+            "pau" | "cl" => PhonemeClass::Silence,
+            "A" | "I" | "U" | "E" | "O" => PhonemeClass::Unvoiced,
+            _ => PhonemeClass::Voiced,
+        }
+    }
+
+    fn of_consonant(consonant: &str) -> Self {
+        if UNVOICED_CONSONANTS.contains(&consonant) {
+            PhonemeClass::Unvoiced
+        } else {
+            PhonemeClass::Voiced
+        }
+    }
+
+    fn cost(&self, frame: &Frame) -> f32 {
+        let quiet = frame.energy < SILENCE_THRESHOLD;
+        match self {
+            PhonemeClass::Silence => (frame.energy * 5.0).min(1.0),
+            PhonemeClass::Voiced => 1.0 - frame.voicing + if quiet { 0.5 } else { 0.0 },
+            PhonemeClass::Unvoiced => frame.voicing * 0.5 + if quiet { 0.3 } else { 0.0 },
+        }
+    }
+}
+
+/// 位置合わせの対象となる音素。
+struct Target {
+    accent_phrase: usize,
+    /// `None`ならポーズ。
+    mora: Option<usize>,
+    is_consonant: bool,
+    class: PhonemeClass,
+    expected: f32,
+}
+
+fn targets(query: &AudioQuery) -> Vec<Target> {
+    let speed_scale = if query.speed_scale > 0.0 {
+        query.speed_scale
+    } else {
+        1.0
+    };
+    let mut targets = vec![];
+    for (i, accent_phrase) in query.accent_phrases.iter().enumerate() {
+        let moras = accent_phrase
+            .moras
+            .iter()
+            .map(Some)
+            .enumerate()
+            .map(|(j, mora)| (Some(j), mora));
+        for (j, mora) in moras.chain(std::iter::once((None, accent_phrase.pause_mora.as_ref()))) {
+            let Some(mora) = mora else { continue };
+            if let (Some(consonant), Some(consonant_length)) = (&mora.consonant, mora.consonant_length) {
+                targets.push(Target {
+                    accent_phrase: i,
+                    mora: j,
+                    is_consonant: true,
+                    class: PhonemeClass::of_consonant(consonant),
+                    expected: consonant_length / speed_scale,
+                });
+            }
+            targets.push(Target {
+                accent_phrase: i,
+                mora: j,
+                is_consonant: false,
+                class: PhonemeClass::of_vowel(&mora.vowel),
+                expected: mora.vowel_length / speed_scale,
+            });
+        }
+    }
+    targets
+}
+
+/// 各音素に割り当てるフレーム数を動的計画法で求める。
+fn segment(targets: &[Target], frames: &[Frame]) -> Vec<usize> {
+    let classes = [PhonemeClass::Silence, PhonemeClass::Voiced, PhonemeClass::Unvoiced];
+    let prefix: Vec<Vec<f32>> = classes
+        .iter()
+        .map(|class| {
+            let mut sums = vec![0.0];
+            for frame in frames {
+                sums.push(sums.last().unwrap() + class.cost(frame));
+            }
+            sums
+        })
+        .collect();
+    let class_index = |class: PhonemeClass| classes.iter().position(|c| *c == class).unwrap();
+
+    let total_frames = frames.len();
+    let expected_total: f32 = targets.iter().map(|target| target.expected).sum();
+    let scale = if expected_total > 0.0 {
+        total_frames as f32 / expected_total
+    } else {
+        1.0
+    };
+
+    let n = targets.len();
+    let mut cost = vec![vec![f32::INFINITY; total_frames + 1]; n + 1];
+    let mut choice = vec![vec![0usize; total_frames + 1]; n + 1];
+    cost[0][0] = 0.0;
+    for (i, target) in targets.iter().enumerate() {
+        let expected = (target.expected * scale).max(1.0);
+        let max_duration = ((expected * 4.0).ceil() as usize).max(3);
+        let sums = &prefix[class_index(target.class)];
+        // 残りの音素に少なくとも1フレームずつ残す。
+        for end in (i + 1)..=(total_frames - (n - i - 1)) {
+            for duration in 1..=max_duration.min(end - i) {
+                let start = end - duration;
+                if cost[i][start].is_infinite() {
+                    continue;
+                }
+                let deviation = (duration as f32 / expected).ln();
+                let candidate = cost[i][start] + sums[end] - sums[start] + DURATION_WEIGHT * deviation * deviation;
+                if candidate < cost[i + 1][end] {
+                    cost[i + 1][end] = candidate;
+                    choice[i + 1][end] = duration;
+                }
+            }
+        }
+    }
+
+    let mut durations = vec![0; n];
+    let mut end = total_frames;
+    for i in (0..n).rev() {
+        durations[i] = choice[i + 1][end];
+        end -= durations[i];
+    }
+    durations
+}
+
+fn median(values: &mut [f32]) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    Some(values[values.len() / 2])
+}
+
+fn mora_mut(query: &mut AudioQuery, accent_phrase: usize, mora: Option<usize>) -> Option<&mut Mora> {
+    let accent_phrase = &mut query.accent_phrases[accent_phrase];
+    match mora {
+        Some(mora) => accent_phrase.moras.get_mut(mora),
+        None => accent_phrase.pause_mora.as_mut(),
+    }
+}
+
+/// 参照音声に合わせてAudioQueryの音素長と音高を書き換える。
+///
+/// `normalize_pitch`が`true`のときは、参照音声の平均の高さをAudioQueryの元の高さに揃える。
+pub fn align(query: &mut AudioQuery, frames: &[Frame], normalize_pitch: bool) -> anyhow::Result<()> {
+    let Some(speech_start) = frames.iter().position(|frame| frame.energy >= SILENCE_THRESHOLD) else {
+        bail!("参照音声に声が含まれていません。");
+    };
+    let speech_end = frames
+        .iter()
+        .rposition(|frame| frame.energy >= SILENCE_THRESHOLD)
+        .unwrap()
+        + 1;
+    let speech = &frames[speech_start..speech_end];
+
+    let targets = targets(query);
+    if targets.len() > speech.len() {
+        bail!("参照音声が短すぎます。");
+    }
+    let durations = segment(&targets, speech);
+
+    let original_pitches: Vec<f32> = query
+        .accent_phrases
+        .iter()
+        .flat_map(|accent_phrase| accent_phrase.moras.iter().map(|mora| mora.pitch))
+        .filter(|pitch| *pitch > 0.0)
+        .collect();
+
+    let mut cursor = 0;
+    let mut guided_pitches = vec![];
+    // 参照音声から音高を取ったモーラ。
+    let mut guided_moras = vec![];
+    for (target, duration) in targets.iter().zip(durations) {
+        let segment_frames = &speech[cursor..cursor + duration];
+        cursor += duration;
+        let Some(mora) = mora_mut(query, target.accent_phrase, target.mora) else {
+            continue;
+        };
+        let length = duration as f32 * FRAME_PERIOD;
+        if target.is_consonant {
+            mora.consonant_length = Some(length);
+            continue;
+        }
+        mora.vowel_length = length;
+        if target.class == PhonemeClass::Voiced {
+            let mut log_f0s: Vec<f32> = segment_frames
+                .iter()
+                .filter_map(|frame| frame.f0)
+                .map(f32::ln)
+                .collect();
+            if let Some(pitch) = median(&mut log_f0s) {
+                mora.pitch = pitch;
+                if let Some(j) = target.mora {
+                    guided_pitches.push(pitch);
+                    guided_moras.push((target.accent_phrase, j));
+                }
+            }
+        } else {
+            mora.pitch = 0.0;
+        }
+    }
+
+    if normalize_pitch && !original_pitches.is_empty() && !guided_pitches.is_empty() {
+        let mean = |values: &[f32]| values.iter().sum::<f32>() / values.len() as f32;
+        let shift = mean(&original_pitches) - mean(&guided_pitches);
+        for (i, j) in guided_moras {
+            query.accent_phrases[i].moras[j].pitch += shift;
+        }
+    }
+
+    // 長さは秒数そのものにしたので、話速は1に戻す。
+    query.speed_scale = 1.0;
+    query.pre_phoneme_length = speech_start as f32 * FRAME_PERIOD;
+    query.post_phoneme_length = (frames.len() - speech_end) as f32 * FRAME_PERIOD;
+    Ok(())
+}
@@ -0,0 +1,337 @@
+use crate::models::{AccentPhrase, Mora};
+use anyhow::bail;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+
+static ACCENT_SYMBOL: char = '\'';
+static NOPAUSE_DELIMITER: char = '/';
+static PAUSE_DELIMITER: char = '、';
+static UNVOICE_SYMBOL: char = '_';
+static WIDE_INTERROGATION_MARK: char = '？';
+
+/// モーラのテキストと子音・母音の対応。本家の`mora_list.py`と同じ。
+static MORA_LIST: &[(&str, &str, &str)] = &[
+    ("ヴォ", "v", "o"),
+    ("ヴェ", "v", "e"),
+    ("ヴィ", "v", "i"),
+    ("ヴァ", "v", "a"),
+    ("ヴ", "v", "u"),
+    ("ン", "", "N"),
+    ("ワ", "w", "a"),
+    ("ロ", "r", "o"),
+    ("レ", "r", "e"),
+    ("ル", "r", "u"),
+    ("リョ", "ry", "o"),
+    ("リュ", "ry", "u"),
+    ("リャ", "ry", "a"),
+    ("リェ", "ry", "e"),
+    ("リ", "r", "i"),
+    ("ラ", "r", "a"),
+    ("ヨ", "y", "o"),
+    ("ユ", "y", "u"),
+    ("ヤ", "y", "a"),
+    ("モ", "m", "o"),
+    ("メ", "m", "e"),
+    ("ム", "m", "u"),
+    ("ミョ", "my", "o"),
+    ("ミュ", "my", "u"),
+    ("ミャ", "my", "a"),
+    ("ミェ", "my", "e"),
+    ("ミ", "m", "i"),
+    ("マ", "m", "a"),
+    ("ポ", "p", "o"),
+    ("ボ", "b", "o"),
+    ("ホ", "h", "o"),
+    ("ペ", "p", "e"),
+    ("ベ", "b", "e"),
+    ("ヘ", "h", "e"),
+    ("プ", "p", "u"),
+    ("ブ", "b", "u"),
+    ("フォ", "f", "o"),
+    ("フェ", "f", "e"),
+    ("フィ", "f", "i"),
+    ("ファ", "f", "a"),
+    ("フ", "f", "u"),
+    ("ピョ", "py", "o"),
+    ("ピュ", "py", "u"),
+    ("ピャ", "py", "a"),
+    ("ピェ", "py", "e"),
+    ("ピ", "p", "i"),
+    ("ビョ", "by", "o"),
+    ("ビュ", "by", "u"),
+    ("ビャ", "by", "a"),
+    ("ビェ", "by", "e"),
+    ("ビ", "b", "i"),
+    ("ヒョ", "hy", "o"),
+    ("ヒュ", "hy", "u"),
+    ("ヒャ", "hy", "a"),
+    ("ヒェ", "hy", "e"),
+    ("ヒ", "h", "i"),
+    ("パ", "p", "a"),
+    ("バ", "b", "a"),
+    ("ハ", "h", "a"),
+    ("ノ", "n", "o"),
+    ("ネ", "n", "e"),
+    ("ヌ", "n", "u"),
+    ("ニョ", "ny", "o"),
+    ("ニュ", "ny", "u"),
+    ("ニャ", "ny", "a"),
+    ("ニェ", "ny", "e"),
+    ("ニ", "n", "i"),
+    ("ナ", "n", "a"),
+    ("ドゥ", "d", "u"),
+    ("ド", "d", "o"),
+    ("トゥ", "t", "u"),
+    ("ト", "t", "o"),
+    ("デョ", "dy", "o"),
+    ("デュ", "dy", "u"),
+    ("デャ", "dy", "a"),
+    ("デェ", "dy", "e"),
+    ("ディ", "d", "i"),
+    ("デ", "d", "e"),
+    ("テョ", "ty", "o"),
+    ("テュ", "ty", "u"),
+    ("テャ", "ty", "a"),
+    ("ティ", "t", "i"),
+    ("テ", "t", "e"),
+    ("ツォ", "ts", "o"),
+    ("ツェ", "ts", "e"),
+    ("ツィ", "ts", "i"),
+    ("ツァ", "ts", "a"),
+    ("ツ", "ts", "u"),
+    ("ッ", "", "cl"),
+    ("チョ", "ch", "o"),
+    ("チュ", "ch", "u"),
+    ("チャ", "ch", "a"),
+    ("チェ", "ch", "e"),
+    ("チ", "ch", "i"),
+    ("ダ", "d", "a"),
+    ("タ", "t", "a"),
+    ("ゾ", "z", "o"),
+    ("ソ", "s", "o"),
+    ("ゼ", "z", "e"),
+    ("セ", "s", "e"),
+    ("ズィ", "z", "i"),
+    ("ズ", "z", "u"),
+    ("スィ", "s", "i"),
+    ("ス", "s", "u"),
+    ("ジョ", "j", "o"),
+    ("ジュ", "j", "u"),
+    ("ジャ", "j", "a"),
+    ("ジェ", "j", "e"),
+    ("ジ", "j", "i"),
+    ("ショ", "sh", "o"),
+    ("シュ", "sh", "u"),
+    ("シャ", "sh", "a"),
+    ("シェ", "sh", "e"),
+    ("シ", "sh", "i"),
+    ("ザ", "z", "a"),
+    ("サ", "s", "a"),
+    ("ゴ", "g", "o"),
+    ("コ", "k", "o"),
+    ("ゲ", "g", "e"),
+    ("ケ", "k", "e"),
+    ("グヮ", "gw", "a"),
+    ("グ", "g", "u"),
+    ("クヮ", "kw", "a"),
+    ("ク", "k", "u"),
+    ("ギョ", "gy", "o"),
+    ("ギュ", "gy", "u"),
+    ("ギャ", "gy", "a"),
+    ("ギェ", "gy", "e"),
+    ("ギ", "g", "i"),
+    ("キョ", "ky", "o"),
+    ("キュ", "ky", "u"),
+    ("キャ", "ky", "a"),
+    ("キェ", "ky", "e"),
+    ("キ", "k", "i"),
+    ("ガ", "g", "a"),
+    ("カ", "k", "a"),
+    ("オ", "", "o"),
+    ("エ", "", "e"),
+    ("ウォ", "w", "o"),
+    ("ウェ", "w", "e"),
+    ("ウィ", "w", "i"),
+    ("ウ", "", "u"),
+    ("イェ", "y", "e"),
+    ("イ", "", "i"),
+    ("ア", "", "a"),
+    ("ヲ", "", "o"),
+];
+
+/// 無声化記号付きのものも含めた、テキストから子音・母音への対応。
+static TEXT_TO_MORA: Lazy<HashMap<String, (&str, String)>> = Lazy::new(|| {
+    let mut map = HashMap::new();
+    for (text, consonant, vowel) in MORA_LIST {
+        map.insert(text.to_string(), (*consonant, vowel.to_string()));
+        if ["a", "i", "u", "e", "o"].contains(vowel) {
+            map.insert(
+                format!("{}{}", UNVOICE_SYMBOL, text),
+                (*consonant, vowel.to_uppercase()),
+            );
+        }
+    }
+    map
+});
+
+/// アクセント句をAquesTalk風記法にする。
+pub fn create_kana(accent_phrases: &[AccentPhrase]) -> String {
+    let mut text = String::new();
+    for (i, accent_phrase) in accent_phrases.iter().enumerate() {
+        for (j, mora) in accent_phrase.moras.iter().enumerate() {
+            if ["A", "I", "U", "E", "O"].contains(&mora.vowel.as_str()) {
+                text.push(UNVOICE_SYMBOL);
+            }
+            text.push_str(&mora.text);
+            if j + 1 == accent_phrase.accent as usize {
+                text.push(ACCENT_SYMBOL);
+            }
+        }
+        if accent_phrase.is_interrogative {
+            text.push(WIDE_INTERROGATION_MARK);
+        }
+        if i < accent_phrases.len() - 1 {
+            if accent_phrase.pause_mora.is_none() {
+                text.push(NOPAUSE_DELIMITER);
+            } else {
+                text.push(PAUSE_DELIMITER);
+            }
+        }
+    }
+    text
+}
+
+fn text_to_accent_phrase(phrase: &[char]) -> anyhow::Result<AccentPhrase> {
+    let mut accent_index = None;
+    let mut moras = vec![];
+    let mut base_index = 0;
+    while base_index < phrase.len() {
+        if phrase[base_index] == ACCENT_SYMBOL {
+            if moras.is_empty() {
+                bail!("句頭にアクセントは置けません：{}", phrase.iter().collect::<String>());
+            }
+            if accent_index.is_some() {
+                bail!(
+                    "1つのアクセント句に二つ以上のアクセントは置けません：{}",
+                    phrase.iter().collect::<String>()
+                );
+            }
+            accent_index = Some(moras.len());
+            base_index += 1;
+            continue;
+        }
+
+        // 最長一致で読む。
+        let mut stack = String::new();
+        let mut matched = None;
+        for (watch_index, c) in phrase.iter().enumerate().skip(base_index) {
+            if *c == ACCENT_SYMBOL {
+                break;
+            }
+            stack.push(*c);
+            if let Some(mora) = TEXT_TO_MORA.get(&stack) {
+                matched = Some((watch_index + 1, stack.clone(), mora));
+            }
+        }
+        let Some((next_index, matched_text, (consonant, vowel))) = matched else {
+            bail!(
+                "判別できない読み仮名があります：{}",
+                phrase[base_index..].iter().collect::<String>()
+            );
+        };
+        moras.push(Mora {
+            text: matched_text.trim_start_matches(UNVOICE_SYMBOL).to_string(),
+            consonant: (!consonant.is_empty()).then(|| consonant.to_string()),
+            consonant_length: (!consonant.is_empty()).then_some(0.0),
+            vowel: vowel.clone(),
+            vowel_length: 0.0,
+            pitch: 0.0,
+        });
+        base_index = next_index;
+    }
+
+    let Some(accent_index) = accent_index else {
+        bail!(
+            "アクセントを指定していないアクセント句があります：{}",
+            phrase.iter().collect::<String>()
+        );
+    };
+    Ok(AccentPhrase {
+        moras,
+        accent: accent_index as u32,
+        pause_mora: None,
+        is_interrogative: false,
+    })
+}
+
+/// AquesTalk風記法をアクセント句にする。音高と音素長は0になる。
+pub fn parse_kana(text: &str) -> anyhow::Result<Vec<AccentPhrase>> {
+    if text.is_empty() {
+        bail!("空のテキストは解析できません。");
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let mut accent_phrases = vec![];
+    let mut phrase_base = 0;
+    for i in 0..=chars.len() {
+        if i < chars.len() && chars[i] != PAUSE_DELIMITER && chars[i] != NOPAUSE_DELIMITER {
+            continue;
+        }
+        let mut phrase = &chars[phrase_base..i];
+        if phrase.is_empty() {
+            bail!("区切り文字が連続しているか、先頭または末尾にあります。");
+        }
+        phrase_base = i + 1;
+
+        let is_interrogative = phrase.last() == Some(&WIDE_INTERROGATION_MARK);
+        if is_interrogative {
+            phrase = &phrase[..phrase.len() - 1];
+        }
+        if phrase.contains(&WIDE_INTERROGATION_MARK) {
+            bail!("疑問符はアクセント句の末尾にのみ置けます。");
+        }
+
+        let mut accent_phrase = text_to_accent_phrase(phrase)?;
+        if i < chars.len() && chars[i] == PAUSE_DELIMITER {
+            accent_phrase.pause_mora = Some(Mora {
+                text: PAUSE_DELIMITER.to_string(),
+                consonant: None,
+                consonant_length: None,
+                vowel: "pau".to_string(),
+                vowel_length: 0.0,
+                pitch: 0.0,
+            });
+        }
+        accent_phrase.is_interrogative = is_interrogative;
+        accent_phrases.push(accent_phrase);
+    }
+    Ok(accent_phrases)
+}
+
+/// カタカナの一モーラ。
+pub static MORA_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        "(?:",
+        "[イ][ェ]|[ヴ][ャュョ]|[トド][ゥ]|[テデ][ィャュョ]|[デ][ェ]|[クグ][ヮ]|", // rule_others
+        "[キシチニヒミリギジビピ][ェャュョ]|",                                    // rule_line_i
+        "[ツフヴ][ァ]|[ウスツフヴズ][ィ]|[ウツフヴ][ェォ]|",                      // rule_line_u
+        "[ァ-ヴー]",                                                              // rule_one_mora
+        ")",
+    ))
+    .unwrap()
+});
+
+/// カタカナの読みのモーラ数。
+pub fn count_moras(pronunciation: &str) -> usize {
+    MORA_REGEX.find_iter(pronunciation).count()
+}
+
+/// ひらがなをカタカナにする。
+pub fn to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ぁ'..='ゖ' => char::from_u32(c as u32 + 0x60).unwrap(),
+            _ => c,
+        })
+        .collect()
+}
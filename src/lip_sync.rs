@@ -0,0 +1,207 @@
+use crate::timeline::PhonemeSpan;
+use serde::Serialize;
+
+/// 口形の名前。VMDのモーフ名（あいうえお）と順番を揃えている。
+pub static VOWELS: [&str; 5] = ["a", "i", "u", "e", "o"];
+
+/// Shift_JISでの「あ」「い」「う」「え」「お」。
+static VMD_MORPH_NAMES: [[u8; 2]; 5] = [[0x82, 0xa0], [0x82, 0xa2], [0x82, 0xa4], [0x82, 0xa6], [0x82, 0xa8]];
+
+/// 無声化した母音の口の開き具合。
+static DEVOICED_WEIGHT: f32 = 0.3;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RhubarbJson {
+    pub metadata: RhubarbMetadata,
+    pub mouth_cues: Vec<RhubarbCue>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RhubarbMetadata {
+    pub sound_file: String,
+    pub duration: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RhubarbCue {
+    pub start: f32,
+    pub end: f32,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CurveJson {
+    pub duration: f32,
+    pub curves: Vec<Curve>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Curve {
+    pub name: String,
+    pub keys: Vec<CurveKey>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CurveKey {
+    pub time: f32,
+    pub value: f32,
+}
+
+/// 音素をRhubarb Lip Syncの口形（A〜H、X）に変換する。
+fn rhubarb_shape(phoneme: &str) -> &'static str {
+    match phoneme {
+        "pau" => "X",
+        "a" | "A" => "D",
+        "i" | "I" => "B",
+        "u" | "U" | "w" => "F",
+        "e" | "E" => "C",
+        "o" | "O" => "E",
+        "N" | "cl" | "m" | "my" | "b" | "by" | "p" | "py" => "A",
+        "f" | "v" => "G",
+        "r" | "ry" => "H",
+        _ => "B",
+    }
+}
+
+/// 音素を「あいうえお」それぞれの開き具合に変換する。子音と無音は口を閉じる。
+fn vowel_weights(phoneme: &str) -> [f32; 5] {
+    let mut weights = [0.0; 5];
+    if let Some(i) = VOWELS.iter().position(|v| *v == phoneme) {
+        weights[i] = 1.0;
+    } else if let Some(i) = VOWELS.iter().position(|v| v.to_uppercase() == phoneme) {
+        weights[i] = DEVOICED_WEIGHT;
+    }
+    weights
+}
+
+pub fn rhubarb_cues(timeline: &[PhonemeSpan]) -> Vec<RhubarbCue> {
+    let mut cues: Vec<RhubarbCue> = vec![];
+    for span in timeline.iter().filter(|span| span.end > span.start) {
+        let shape = rhubarb_shape(&span.phoneme);
+        match cues.last_mut() {
+            Some(last) if last.value == shape => last.end = span.end,
+            _ => cues.push(RhubarbCue {
+                start: span.start,
+                end: span.end,
+                value: shape.to_string(),
+            }),
+        }
+    }
+    cues
+}
+
+pub fn rhubarb_json(timeline: &[PhonemeSpan]) -> RhubarbJson {
+    RhubarbJson {
+        metadata: RhubarbMetadata {
+            sound_file: String::new(),
+            duration: timeline.last().map_or(0.0, |span| span.end),
+        },
+        mouth_cues: rhubarb_cues(timeline),
+    }
+}
+
+/// Rhubarbの`--exportFormat tsv`と同じ形式。最後の行は終了時刻と`X`。
+pub fn rhubarb_tsv(timeline: &[PhonemeSpan]) -> String {
+    let cues = rhubarb_cues(timeline);
+    let mut tsv = String::new();
+    for cue in &cues {
+        tsv.push_str(&format!("{:.2}\t{}\n", cue.start, cue.value));
+    }
+    if let Some(last) = cues.last() {
+        tsv.push_str(&format!("{:.2}\tX\n", last.end));
+    }
+    tsv
+}
+
+/// 「あいうえお」それぞれのキーフレーム列を作る。
+///
+/// 各音素の始点と、終点の`transition`秒前にキーを打って口形を保持する。
+/// 同じ値が続くだけのキーは省く。
+pub fn vowel_curves(timeline: &[PhonemeSpan], transition: f32) -> Vec<Curve> {
+    let mut keys: Vec<(f32, [f32; 5])> = vec![];
+    for span in timeline.iter().filter(|span| span.end > span.start) {
+        let weights = vowel_weights(&span.phoneme);
+        keys.push((span.start, weights));
+        if span.end - transition > span.start {
+            keys.push((span.end - transition, weights));
+        }
+    }
+    if let Some(last) = timeline.last() {
+        keys.push((last.end, [0.0; 5]));
+    }
+
+    VOWELS
+        .iter()
+        .enumerate()
+        .map(|(i, vowel)| {
+            let track: Vec<CurveKey> = keys
+                .iter()
+                .map(|(time, weights)| CurveKey {
+                    time: *time,
+                    value: weights[i],
+                })
+                .collect();
+            let keys = track
+                .iter()
+                .enumerate()
+                .filter(|(j, key)| {
+                    let prev = j.checked_sub(1).map(|j| track[j].value);
+                    let next = track.get(j + 1).map(|next| next.value);
+                    prev != Some(key.value) || next != Some(key.value)
+                })
+                .map(|(_, key)| *key)
+                .collect();
+            Curve {
+                name: vowel.to_string(),
+                keys,
+            }
+        })
+        .collect()
+}
+
+pub fn curve_json(timeline: &[PhonemeSpan], fps: f32) -> CurveJson {
+    CurveJson {
+        duration: timeline.last().map_or(0.0, |span| span.end),
+        curves: vowel_curves(timeline, 1.0 / fps),
+    }
+}
+
+/// MMDのモーションファイル（VMD）として書き出す。モーフのキーフレームのみを含む。
+pub fn vmd(timeline: &[PhonemeSpan], fps: f32) -> Vec<u8> {
+    let mut morph_frames: Vec<(&[u8; 2], u32, f32)> = vec![];
+    for (name, curve) in VMD_MORPH_NAMES.iter().zip(vowel_curves(timeline, 1.0 / fps)) {
+        for key in curve.keys {
+            let frame = (key.time * fps).round() as u32;
+            // 同じフレームに複数のキーがある場合は後のものを優先する。
+            match morph_frames.last_mut() {
+                Some(last) if last.0 == name && last.1 == frame => last.2 = key.value,
+                _ => morph_frames.push((name, frame, key.value)),
+            }
+        }
+    }
+
+    let mut buf = vec![];
+    write_fixed(&mut buf, b"Vocaloid Motion Data 0002", 30);
+    write_fixed(&mut buf, b"", 20);
+    // ボーン
+    buf.extend_from_slice(&0u32.to_le_bytes());
+    // モーフ
+    buf.extend_from_slice(&(morph_frames.len() as u32).to_le_bytes());
+    for (name, frame, weight) in morph_frames {
+        write_fixed(&mut buf, name, 15);
+        buf.extend_from_slice(&frame.to_le_bytes());
+        buf.extend_from_slice(&weight.to_le_bytes());
+    }
+    // カメラ、照明、セルフ影、IK
+    for _ in 0..4 {
+        buf.extend_from_slice(&0u32.to_le_bytes());
+    }
+    buf
+}
+
+fn write_fixed(buf: &mut Vec<u8>, bytes: &[u8], len: usize) {
+    buf.extend_from_slice(&bytes[..bytes.len().min(len)]);
+    buf.resize(buf.len() + len.saturating_sub(bytes.len()), 0);
+}
@@ -1,9 +1,26 @@
+mod accent_edit;
+mod chunk;
+mod dict_format;
+mod dict_history;
+mod dict_index;
+mod dict_store;
+mod dict_suggest;
+mod english;
+mod guide;
+mod kana;
+mod lip_sync;
 mod models;
+mod normalize;
+mod pitch_edit;
 mod resource_manager;
 mod result;
 mod routes;
+mod ssml;
+mod subtitle;
+mod timeline;
 mod utils;
 mod vvm_manager;
+mod wav;
 
 use crate::{
     resource_manager::{ResourceManager, RESOURCE_MANAGER},
@@ -105,21 +122,57 @@ async fn main() {
         .route("/supported_devices", get(routes::supported_devices_get))
         .route("/speakers", get(routes::speakers_get))
         .route("/speaker_info", get(routes::speaker_info_get))
+        .route("/styles", get(routes::styles_get))
         .route("/user_dict", get(routes::user_dict_get))
+        .route("/user_dict/search", get(routes::user_dict_search_get))
+        .route("/user_dict/batch", post(routes::user_dict_batch_post))
+        .route("/user_dict/history", get(routes::user_dict_history_get))
+        .route("/user_dict/history/restore", post(routes::user_dict_history_restore_post))
+        .route("/user_dict/history/:id/revert", post(routes::user_dict_history_revert_post))
+        .route("/user_dicts", get(routes::user_dicts_get))
+        .route(
+            "/user_dicts/:name",
+            get(routes::named_user_dict_get)
+                .post(routes::named_user_dict_post)
+                .put(routes::named_user_dict_put)
+                .delete(routes::named_user_dict_delete),
+        )
+        .route("/user_dict/backups", get(routes::user_dict_backups_get))
+        .route(
+            "/user_dict/backups/:backup_name/restore",
+            post(routes::user_dict_backup_restore_post),
+        )
         .route("/import_user_dict", post(routes::import_user_dict_post))
+        .route("/import_user_dict_file", post(routes::import_user_dict_file_post))
+        .route("/export_user_dict", get(routes::export_user_dict_get))
+        .route("/user_dict_suggestions", post(routes::user_dict_suggestions_post))
         .route("/user_dict_word", post(routes::user_dict_word_post))
+        .route("/preview_user_dict_word", post(routes::user_dict_word_preview_post))
         .route(
             "/user_dict_word/:word_uuid",
             put(routes::user_dict_word_put).delete(routes::user_dict_word_delete),
         )
         .route("/is_initialized_speaker", get(routes::is_initialized_speaker_get))
         .route("/initialize_speaker", post(routes::initialize_speaker_post))
+        .route("/normalize_text", post(routes::normalize_text_post))
+        .route("/reading_debug", post(routes::reading_debug_post))
         .route("/audio_query", post(routes::audio_query_post))
         .route("/accent_phrases", post(routes::accent_phrases_post))
         .route("/mora_data", post(routes::mora_data_post))
         .route("/mora_pitch", post(routes::mora_pitch_post))
         .route("/mora_length", post(routes::mora_length_post))
+        .route("/accent_phrase_edit", post(routes::accent_phrase_edit_post))
+        .route("/pitch_edit", post(routes::pitch_edit_post))
+        .route("/accent_phrases_to_kana", post(routes::accent_phrases_to_kana_post))
+        .route("/kana_to_accent_phrases", post(routes::kana_to_accent_phrases_post))
+        .route("/guided_accent_phrases", post(routes::guided_accent_phrases_post))
         .route("/synthesis", post(routes::synthesis_post))
+        .route("/guided_synthesis", post(routes::guided_synthesis_post))
+        .route("/lip_sync", post(routes::lip_sync_post))
+        .route("/subtitles", post(routes::subtitles_post))
+        .route("/ssml_audio_query", post(routes::ssml_audio_query_post))
+        .route("/ssml_synthesis", post(routes::ssml_synthesis_post))
+        .route("/script_synthesis", post(routes::script_synthesis_post))
         .layer(cors)
         .layer(
             TraceLayer::new_for_http()
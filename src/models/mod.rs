@@ -1,7 +1,9 @@
 mod engine_manifest;
 mod speaker_info;
 mod audio_query;
+mod user_dict_word;
 
 pub use engine_manifest::*;
 pub use speaker_info::*;
 pub use audio_query::*;
+pub use user_dict_word::*;
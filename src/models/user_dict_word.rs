@@ -0,0 +1,100 @@
+use crate::kana::count_moras;
+use serde::{Deserialize, Serialize};
+use voicevox_core_rs::{UserDictWord, UserDictWordType};
+
+/// 品詞。OpenJTalkでの単語の扱われ方が変わる。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WordType {
+    #[default]
+    ProperNoun,
+    CommonNoun,
+    Verb,
+    Adjective,
+    Suffix,
+}
+
+impl From<WordType> for UserDictWordType {
+    fn from(word_type: WordType) -> UserDictWordType {
+        match word_type {
+            WordType::ProperNoun => UserDictWordType::ProperNoun,
+            WordType::CommonNoun => UserDictWordType::CommonNoun,
+            WordType::Verb => UserDictWordType::Verb,
+            WordType::Adjective => UserDictWordType::Adjective,
+            WordType::Suffix => UserDictWordType::Suffix,
+        }
+    }
+}
+
+impl From<UserDictWordType> for WordType {
+    fn from(word_type: UserDictWordType) -> WordType {
+        match word_type {
+            UserDictWordType::ProperNoun => WordType::ProperNoun,
+            UserDictWordType::CommonNoun => WordType::CommonNoun,
+            UserDictWordType::Verb => WordType::Verb,
+            UserDictWordType::Adjective => WordType::Adjective,
+            UserDictWordType::Suffix => WordType::Suffix,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VvUserDictWord {
+    priority: u32,
+    accent_type: usize,
+    mora_count: usize,
+    surface: String,
+    pronunciation: String,
+    /// 品詞の無い古い辞書は固有名詞として読む。
+    #[serde(default)]
+    word_type: WordType,
+}
+
+impl VvUserDictWord {
+    pub fn surface(&self) -> &str {
+        &self.surface
+    }
+
+    pub fn pronunciation(&self) -> &str {
+        &self.pronunciation
+    }
+
+    pub fn accent_type(&self) -> usize {
+        self.accent_type
+    }
+
+    pub fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    pub fn word_type(&self) -> WordType {
+        self.word_type
+    }
+}
+
+impl From<UserDictWord> for VvUserDictWord {
+    fn from(word: UserDictWord) -> VvUserDictWord {
+        VvUserDictWord::from(&word)
+    }
+}
+impl From<&UserDictWord> for VvUserDictWord {
+    fn from(word: &UserDictWord) -> VvUserDictWord {
+        VvUserDictWord {
+            priority: word.priority,
+            accent_type: word.accent_type,
+            mora_count: count_moras(&word.pronunciation),
+            surface: word.surface.clone(),
+            pronunciation: word.pronunciation.clone(),
+            word_type: word.word_type.into(),
+        }
+    }
+}
+impl From<VvUserDictWord> for UserDictWord {
+    fn from(word: VvUserDictWord) -> UserDictWord {
+        let mut w = UserDictWord::new(&word.surface[..], &word.pronunciation);
+        w.accent_type = word.accent_type;
+        w.word_type = word.word_type.into();
+        w.priority = word.priority;
+        w
+    }
+}
@@ -0,0 +1,412 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::warn;
+
+pub static NORMALIZE_RULES_PATH: &str = "./normalize_rules.json";
+
+pub static LETTER_READINGS: [&str; 26] = [
+    "エー",
+    "ビー",
+    "シー",
+    "ディー",
+    "イー",
+    "エフ",
+    "ジー",
+    "エイチ",
+    "アイ",
+    "ジェー",
+    "ケー",
+    "エル",
+    "エム",
+    "エヌ",
+    "オー",
+    "ピー",
+    "キュー",
+    "アール",
+    "エス",
+    "ティー",
+    "ユー",
+    "ブイ",
+    "ダブリュー",
+    "エックス",
+    "ワイ",
+    "ゼット",
+];
+pub static DIGIT_READINGS: [&str; 10] = [
+    "ゼロ",
+    "イチ",
+    "ニ",
+    "サン",
+    "ヨン",
+    "ゴ",
+    "ロク",
+    "ナナ",
+    "ハチ",
+    "キュウ",
+];
+
+/// 正規化の各段階。この順に適用する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    /// 全角英数字を半角にする。
+    Width,
+    /// ルールファイルの置換。
+    Rules,
+    Url,
+    Email,
+    Date,
+    Time,
+    Currency,
+    Unit,
+    Symbol,
+    Number,
+    /// 大文字の略語を一文字ずつ読む。
+    Acronym,
+    /// 文末などの「w」を「ワラ」にする。
+    Laughter,
+    /// 絵文字を取り除く。
+    Emoji,
+}
+
+static STEPS: [Step; 13] = [
+    Step::Width,
+    Step::Rules,
+    Step::Url,
+    Step::Email,
+    Step::Date,
+    Step::Time,
+    Step::Currency,
+    Step::Unit,
+    Step::Symbol,
+    Step::Number,
+    Step::Acronym,
+    Step::Laughter,
+    Step::Emoji,
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    /// 正規表現。
+    pub pattern: String,
+    /// `$1`などでキャプチャを参照できる。
+    pub replacement: String,
+}
+
+/// ルールファイルの中身。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NormalizeConfig {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub disabled_steps: Vec<Step>,
+}
+
+struct Normalizer {
+    rules: Vec<(Regex, String)>,
+    disabled_steps: Vec<Step>,
+}
+
+static NORMALIZER: Lazy<Normalizer> = Lazy::new(|| {
+    let config = match std::fs::read_to_string(NORMALIZE_RULES_PATH) {
+        Ok(json) => serde_json::from_str::<NormalizeConfig>(&json).unwrap_or_else(|e| {
+            warn!("Failed to parse normalize rules from {:?}: {}", NORMALIZE_RULES_PATH, e);
+            NormalizeConfig::default()
+        }),
+        Err(_) => NormalizeConfig::default(),
+    };
+    let rules = config
+        .rules
+        .into_iter()
+        .filter_map(|rule| match Regex::new(&rule.pattern) {
+            Ok(regex) => Some((regex, rule.replacement)),
+            Err(e) => {
+                warn!("Invalid normalize rule {:?}: {}", rule.pattern, e);
+                None
+            }
+        })
+        .collect();
+    Normalizer {
+        rules,
+        disabled_steps: config.disabled_steps,
+    }
+});
+
+static URL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://[A-Za-z0-9\-._~:/?#\[\]@!$&'()*+,;=%]+").unwrap());
+static EMAIL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9\-]+(?:\.[A-Za-z0-9\-]+)+").unwrap());
+static DATE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"([0-9]{4})[/\-.]([0-9]{1,2})[/\-.]([0-9]{1,2})").unwrap());
+/// 数字の並びをまとめて取り、時と分が桁数も範囲も合うときだけ時刻として読む。「3:2」や「10:100」のような比や得点は読み替えない。
+static TIME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"([0-9]+):([0-9]+)(?::([0-9]+))?").unwrap());
+static CURRENCY_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"([¥$€£])\s?([0-9][0-9,]*(?:\.[0-9]+)?)").unwrap());
+static UNIT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"([0-9][0-9,]*(?:\.[0-9]+)?)\s?(km|kg|kHz|cm|mm|mg|ml|mL|GB|MB|KB|TB|Hz|°C|℃|m|g|L|%)([^A-Za-z]|$)")
+        .unwrap()
+});
+static RANGE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"([0-9])\s?[~〜～]\s?([0-9])").unwrap());
+static THOUSANDS_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[0-9]{1,3}(?:,[0-9]{3})+").unwrap());
+static DECIMAL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"([0-9]+)\.([0-9]+)").unwrap());
+static NEGATIVE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(^|[^0-9A-Za-z])[-−]([0-9])").unwrap());
+static WORD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[A-Za-z]+").unwrap());
+static LAUGHTER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"([\p{Hiragana}\p{Katakana}\p{Han}ー！？!?。、…])w+([^A-Za-z]|$)").unwrap());
+
+/// 半角にする全角記号。
+static FULL_WIDTH_SYMBOLS: &str = "％＆＋＝＃＠＄：．，／－";
+
+fn unit_reading(unit: &str) -> &'static str {
+    match unit {
+        "km" => "キロメートル",
+        "kg" => "キログラム",
+        "kHz" => "キロヘルツ",
+        "cm" => "センチメートル",
+        "mm" => "ミリメートル",
+        "mg" => "ミリグラム",
+        "ml" | "mL" => "ミリリットル",
+        "GB" => "ギガバイト",
+        "MB" => "メガバイト",
+        "KB" => "キロバイト",
+        "TB" => "テラバイト",
+        "Hz" => "ヘルツ",
+        "°C" | "℃" => "度",
+        "m" => "メートル",
+        "g" => "グラム",
+        "L" => "リットル",
+        "%" => "パーセント",
+        _ => unreachable!(),
+    }
+}
+
+fn symbol_reading(c: char) -> Option<&'static str> {
+    Some(match c {
+        '&' => "アンド",
+        '+' => "プラス",
+        '=' => "イコール",
+        '#' => "シャープ",
+        '@' => "アット",
+        '%' => "パーセント",
+        '×' => "カケル",
+        '÷' => "ワル",
+        '→' => "、",
+        _ => return None,
+    })
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B50 | 0x2B55 | 0xFE0F | 0x200D)
+}
+
+/// 先頭の0を落とす。
+fn trim_zeros(digits: &str) -> &str {
+    let trimmed = digits.trim_start_matches('0');
+    if trimmed.is_empty() {
+        "0"
+    } else {
+        trimmed
+    }
+}
+
+pub fn spell_out(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii_alphabetic() {
+                LETTER_READINGS[(c.to_ascii_uppercase() as u8 - b'A') as usize].to_string()
+            } else if let Some(digit) = c.to_digit(10) {
+                DIGIT_READINGS[digit as usize].to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+fn apply_step(step: Step, text: &str) -> String {
+    match step {
+        Step::Width => text
+            .chars()
+            .map(|c| match c {
+                '０'..='９' | 'Ａ'..='Ｚ' | 'ａ'..='ｚ' => char::from_u32(c as u32 - 0xFEE0).unwrap(),
+                c if FULL_WIDTH_SYMBOLS.contains(c) => char::from_u32(c as u32 - 0xFEE0).unwrap(),
+                '￥' => '¥',
+                _ => c,
+            })
+            .collect(),
+        Step::Rules => NORMALIZER
+            .rules
+            .iter()
+            .fold(text.to_string(), |text, (regex, replacement)| {
+                regex.replace_all(&text, replacement.as_str()).into_owned()
+            }),
+        Step::Url => URL_REGEX.replace_all(text, "ユーアールエル").into_owned(),
+        Step::Email => EMAIL_REGEX.replace_all(text, "メールアドレス").into_owned(),
+        Step::Date => DATE_REGEX
+            .replace_all(text, |caps: &Captures| {
+                match (caps[2].parse::<u32>(), caps[3].parse::<u32>()) {
+                    (Ok(month), Ok(day)) if (1..=12).contains(&month) && (1..=31).contains(&day) => {
+                        format!("{}年{}月{}日", &caps[1], month, day)
+                    }
+                    _ => caps[0].to_string(),
+                }
+            })
+            .into_owned(),
+        Step::Time => TIME_REGEX
+            .replace_all(text, |caps: &Captures| {
+                let is_time = caps[1].len() <= 2
+                    && caps[2].len() == 2
+                    && caps.get(3).is_none_or(|second| second.as_str().len() == 2);
+                let (Ok(hour), Ok(minute), Ok(second)) = (
+                    caps[1].parse::<u32>(),
+                    caps[2].parse::<u32>(),
+                    caps.get(3).map(|second| second.as_str().parse::<u32>()).transpose(),
+                ) else {
+                    return caps[0].to_string();
+                };
+                if !is_time || hour > 24 || minute >= 60 || second.is_some_and(|second| second >= 60) {
+                    return caps[0].to_string();
+                }
+                let mut time = format!("{}時", hour);
+                if minute > 0 {
+                    time += &format!("{}分", minute);
+                }
+                if let Some(second) = second.filter(|second| *second > 0) {
+                    time += &format!("{}秒", second);
+                }
+                time
+            })
+            .into_owned(),
+        Step::Currency => CURRENCY_REGEX
+            .replace_all(text, |caps: &Captures| {
+                let unit = match &caps[1] {
+                    "¥" => "円",
+                    "$" => "ドル",
+                    "€" => "ユーロ",
+                    _ => "ポンド",
+                };
+                format!("{}{}", &caps[2], unit)
+            })
+            .into_owned(),
+        Step::Unit => UNIT_REGEX
+            .replace_all(text, |caps: &Captures| {
+                format!("{}{}{}", &caps[1], unit_reading(&caps[2]), &caps[3])
+            })
+            .into_owned(),
+        Step::Symbol => {
+            let text = RANGE_REGEX.replace_all(text, "${1}から${2}");
+            text.chars()
+                .map(|c| match symbol_reading(c) {
+                    Some(reading) => reading.to_string(),
+                    None => c.to_string(),
+                })
+                .collect()
+        }
+        Step::Number => {
+            let text = THOUSANDS_REGEX.replace_all(text, |caps: &Captures| caps[0].replace(',', ""));
+            let text = DECIMAL_REGEX.replace_all(&text, |caps: &Captures| {
+                format!("{}テン{}", trim_zeros(&caps[1]), spell_out(&caps[2]))
+            });
+            NEGATIVE_REGEX.replace_all(&text, "${1}マイナス${2}").into_owned()
+        }
+        Step::Acronym => WORD_REGEX
+            .replace_all(text, |caps: &Captures| {
+                let word = &caps[0];
+                if (2..=6).contains(&word.len()) && word.chars().all(|c| c.is_ascii_uppercase()) {
+                    spell_out(word)
+                } else {
+                    word.to_string()
+                }
+            })
+            .into_owned(),
+        Step::Laughter => LAUGHTER_REGEX.replace_all(text, "${1}ワラ${2}").into_owned(),
+        Step::Emoji => text.chars().filter(|c| !is_emoji(*c)).collect(),
+    }
+}
+
+/// 各段階を適用し、変化した段階とその結果を返す。
+pub fn normalize_with_trace(text: &str) -> (String, Vec<(Step, String)>) {
+    let mut text = text.to_string();
+    let mut trace = vec![];
+    for step in STEPS {
+        if NORMALIZER.disabled_steps.contains(&step) {
+            continue;
+        }
+        let normalized = apply_step(step, &text);
+        if normalized != text {
+            trace.push((step, normalized.clone()));
+            text = normalized;
+        }
+    }
+    (text, trace)
+}
+
+/// OpenJTalkに渡す前にテキストを読みやすい形にする。
+pub fn normalize(text: &str) -> String {
+    normalize_with_trace(text).0
+}
+
+/// 正規化の間、保護した語の代わりに置く文字（私用領域）。
+static PLACEHOLDER_START: u32 = 0xE000;
+static PLACEHOLDER_END: u32 = 0xF8FF;
+
+/// `protected`に含まれる語（ユーザー辞書の表記）には手を付けずに正規化する。
+///
+/// 表記は全角で保存されているので、半角にしたものも探す。
+pub fn normalize_protected(text: &str, protected: &HashSet<String>) -> String {
+    let is_placeholder = |c: char| (PLACEHOLDER_START..=PLACEHOLDER_END).contains(&(c as u32));
+    let surfaces: HashSet<String> = protected
+        .iter()
+        .flat_map(|surface| [surface.clone(), apply_step(Step::Width, surface)])
+        .filter(|surface| !surface.is_empty() && text.contains(surface.as_str()))
+        .collect();
+    if surfaces.is_empty() || text.chars().any(is_placeholder) {
+        return normalize(text);
+    }
+    // 長いものを先に試す。
+    let mut surfaces: Vec<String> = surfaces.into_iter().collect();
+    surfaces.sort_by_key(|surface| std::cmp::Reverse(surface.chars().count()));
+    let pattern = surfaces
+        .iter()
+        .map(|surface| regex::escape(surface))
+        .collect::<Vec<_>>()
+        .join("|");
+    let Ok(regex) = Regex::new(&pattern) else {
+        return normalize(text);
+    };
+
+    let mut originals: Vec<String> = vec![];
+    let masked = regex.replace_all(text, |caps: &Captures| {
+        let Some(placeholder) =
+            char::from_u32(PLACEHOLDER_START + originals.len() as u32).filter(|c| is_placeholder(*c))
+        else {
+            return caps[0].to_string();
+        };
+        originals.push(caps[0].to_string());
+        placeholder.to_string()
+    });
+    normalize(&masked)
+        .chars()
+        .map(|c| {
+            let index = (c as u32).wrapping_sub(PLACEHOLDER_START) as usize;
+            match originals.get(index) {
+                Some(original) if is_placeholder(c) => original.clone(),
+                _ => c.to_string(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time() {
+        assert_eq!(apply_step(Step::Time, "9:05に"), "9時5分に");
+        assert_eq!(apply_step(Step::Time, "12:00:30"), "12時30秒");
+        assert_eq!(apply_step(Step::Time, "3:2の比"), "3:2の比");
+        assert_eq!(apply_step(Step::Time, "10:100"), "10:100");
+        assert_eq!(apply_step(Step::Time, "123:45"), "123:45");
+        assert_eq!(apply_step(Step::Time, "12:75"), "12:75");
+    }
+}
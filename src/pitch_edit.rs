@@ -0,0 +1,139 @@
+use crate::models::{AccentPhrase, AudioQuery, Mora};
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+/// 音高への編集操作。音高は対数F0なので、値の加算は周波数の掛け算になる。
+///
+/// 無声のモーラ（音高0）は変更しない。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PitchOperation {
+    /// 句全体の音高をずらす。`accent_phrase_index`を省くと全体をずらす。
+    Offset {
+        accent_phrase_index: Option<usize>,
+        offset: f32,
+    },
+    /// 先頭からの経過時間に比例して音高を下げる。`slope`は1秒あたりの下げ幅。
+    Declination { slope: f32 },
+    /// 句末の`mora_count`モーラを、最後のモーラで`amount`になるよう徐々に上げる。
+    QuestionRise {
+        accent_phrase_index: usize,
+        mora_count: usize,
+        amount: f32,
+    },
+    /// `start`番目から`end`番目の手前までのモーラを強調する。0始まり。
+    Emphasis {
+        accent_phrase_index: usize,
+        start: usize,
+        end: usize,
+        amount: f32,
+    },
+    /// 連続する有声モーラの音高を移動平均でならす。`radius`は前後何モーラを見るか。
+    Smooth { radius: usize },
+}
+
+fn get_mut(accent_phrases: &mut [AccentPhrase], index: usize) -> anyhow::Result<&mut AccentPhrase> {
+    accent_phrases
+        .get_mut(index)
+        .with_context(|| format!("アクセント句が見つかりません：{}", index))
+}
+
+fn shift(mora: &mut Mora, amount: f32) {
+    if mora.pitch > 0.0 {
+        mora.pitch += amount;
+    }
+}
+
+fn mora_length(mora: &Mora) -> f32 {
+    mora.consonant_length.unwrap_or(0.0) + mora.vowel_length
+}
+
+pub fn apply(audio_query: &mut AudioQuery, operation: &PitchOperation) -> anyhow::Result<()> {
+    let accent_phrases = &mut audio_query.accent_phrases;
+    match *operation {
+        PitchOperation::Offset {
+            accent_phrase_index,
+            offset,
+        } => {
+            let targets = match accent_phrase_index {
+                Some(index) => std::slice::from_mut(get_mut(accent_phrases, index)?),
+                None => &mut accent_phrases[..],
+            };
+            for mora in targets.iter_mut().flat_map(|accent_phrase| &mut accent_phrase.moras) {
+                shift(mora, offset);
+            }
+        }
+        PitchOperation::Declination { slope } => {
+            let speed_scale = if audio_query.speed_scale > 0.0 {
+                audio_query.speed_scale
+            } else {
+                1.0
+            };
+            let mut time = 0.0;
+            for accent_phrase in accent_phrases.iter_mut() {
+                for mora in &mut accent_phrase.moras {
+                    shift(mora, -slope * time);
+                    time += mora_length(mora) / speed_scale;
+                }
+                if let Some(pause_mora) = &accent_phrase.pause_mora {
+                    time += mora_length(pause_mora) / speed_scale;
+                }
+            }
+        }
+        PitchOperation::QuestionRise {
+            accent_phrase_index,
+            mora_count,
+            amount,
+        } => {
+            let accent_phrase = get_mut(accent_phrases, accent_phrase_index)?;
+            let moras = &mut accent_phrase.moras;
+            if mora_count == 0 || mora_count > moras.len() {
+                bail!("上げるモーラ数が不正です：{}", mora_count);
+            }
+            let start = moras.len() - mora_count;
+            for (i, mora) in moras[start..].iter_mut().enumerate() {
+                shift(mora, amount * (i + 1) as f32 / mora_count as f32);
+            }
+        }
+        PitchOperation::Emphasis {
+            accent_phrase_index,
+            start,
+            end,
+            amount,
+        } => {
+            let accent_phrase = get_mut(accent_phrases, accent_phrase_index)?;
+            if start >= end || end > accent_phrase.moras.len() {
+                bail!("強調する範囲が不正です：{}..{}", start, end);
+            }
+            for mora in &mut accent_phrase.moras[start..end] {
+                shift(mora, amount);
+            }
+        }
+        PitchOperation::Smooth { radius } => {
+            let mut moras: Vec<&mut Mora> = vec![];
+            for accent_phrase in accent_phrases.iter_mut() {
+                moras.extend(&mut accent_phrase.moras);
+                // ポーズを挟んだら別の区間にする。
+                if let Some(pause_mora) = &mut accent_phrase.pause_mora {
+                    moras.push(pause_mora);
+                }
+            }
+            let pitches: Vec<f32> = moras.iter().map(|mora| mora.pitch).collect();
+            for (i, mora) in moras.into_iter().enumerate() {
+                if pitches[i] <= 0.0 {
+                    continue;
+                }
+                // 無声のモーラを越えない範囲で平均する。
+                let before = pitches[..i].iter().rev().take(radius).take_while(|pitch| **pitch > 0.0);
+                let after = pitches[i + 1..].iter().take(radius).take_while(|pitch| **pitch > 0.0);
+                let window: Vec<f32> = before
+                    .chain(after)
+                    .copied()
+                    .chain(std::iter::once(pitches[i]))
+                    .collect();
+                mora.pitch = window.iter().sum::<f32>() / window.len() as f32;
+            }
+        }
+    }
+    Ok(())
+}
@@ -3,25 +3,95 @@ use axum::{
     Json,
 };
 use http::StatusCode;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-pub struct Error(pub String);
+#[derive(Debug)]
+pub struct Error {
+    pub status: StatusCode,
+    pub message: String,
+    /// 項目ごとのエラー。無ければ空。
+    pub fields: Vec<FieldError>,
+}
+
+/// 入力の項目ごとのエラー。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Error {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Error {
+            status,
+            message: message.into(),
+            fields: vec![],
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Error::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Error::new(StatusCode::NOT_FOUND, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Error::new(StatusCode::CONFLICT, message)
+    }
+
+    pub fn payload_too_large(message: impl Into<String>) -> Self {
+        Error::new(StatusCode::PAYLOAD_TOO_LARGE, message)
+    }
+
+    pub fn unprocessable_entity(fields: Vec<FieldError>) -> Self {
+        let message = fields
+            .iter()
+            .map(|field| format!("{}: {}", field.field, field.message))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Error {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            message,
+            fields,
+        }
+    }
+}
 
 impl From<anyhow::Error> for Error {
     fn from(e: anyhow::Error) -> Self {
-        Error(format!("{:?}", e))
+        Error::new(StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e))
     }
 }
 
 #[derive(Serialize)]
 pub struct ErrorJson {
     pub error: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorJson { error: self.0 })).into_response()
+        (
+            self.status,
+            Json(ErrorJson {
+                error: self.message,
+                fields: self.fields,
+            }),
+        )
+            .into_response()
     }
 }
@@ -0,0 +1,29 @@
+use crate::{
+    accent_edit::{self, AccentPhraseOperation},
+    models::AccentPhrase,
+    result::{Error, Result},
+    routes::{styles::Style, synthesis::replace_mora_data},
+};
+
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccentPhraseEditRequest {
+    accent_phrases: Vec<AccentPhrase>,
+    /// 先頭から順に適用する。
+    operations: Vec<AccentPhraseOperation>,
+}
+
+/// アクセント句を編集し、音高と音素長を再予測して返す。
+pub async fn accent_phrase_edit_post(
+    Style(speaker): Style,
+    Json(request): Json<AccentPhraseEditRequest>,
+) -> Result<Json<Vec<AccentPhrase>>> {
+    let mut accent_phrases = request.accent_phrases;
+    for operation in &request.operations {
+        accent_edit::apply(&mut accent_phrases, operation).map_err(|e| Error::bad_request(e.to_string()))?;
+    }
+
+    Ok(Json(replace_mora_data(&accent_phrases, speaker).await?))
+}
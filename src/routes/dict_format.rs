@@ -0,0 +1,133 @@
+use crate::{
+    dict_format::{self, DictFormat, Row},
+    dict_suggest::guess_accent_type,
+    kana::{count_moras, to_katakana},
+    result::{Error, FieldError, Result},
+    routes::{
+        dict_history::Author,
+        user_dict::{add_user_dict_words, user_dict_words, VvUserDictWordParam},
+    },
+};
+
+use axum::{
+    extract::Query,
+    response::{IntoResponse, Response},
+    Json,
+};
+use http::header;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use voicevox_core_rs::UserDictWord;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportUserDictQuery {
+    format: DictFormat,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportUserDictFileQuery {
+    format: DictFormat,
+    /// `true`なら検証と報告のみ行い、辞書は変更しない。
+    dry_run: Option<bool>,
+}
+
+/// 取り込めなかった行。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowError {
+    line: usize,
+    message: String,
+    fields: Vec<FieldError>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportUserDictFileReport {
+    /// 追加した単語のUUID。`dry_run`のときは空。
+    added: Vec<String>,
+    /// 追加できる行の行番号。
+    accepted_lines: Vec<usize>,
+    /// 同じ表記と読みの単語が既にあるため飛ばした行の行番号。
+    skipped_lines: Vec<usize>,
+    errors: Vec<RowError>,
+    dry_run: bool,
+}
+
+/// ユーザー辞書を指定した形式のファイルとして返す。
+pub async fn export_user_dict_get(Query(query): Query<ExportUserDictQuery>) -> Response {
+    let mut rows: Vec<Row> = user_dict_words()
+        .await
+        .into_values()
+        .map(|word| Row {
+            surface: word.surface().to_string(),
+            pronunciation: word.pronunciation().to_string(),
+            accent_type: Some(word.accent_type()),
+            priority: Some(word.priority()),
+            word_type: Some(word.word_type()),
+        })
+        .collect();
+    rows.sort_by(|a, b| (&a.surface, &a.pronunciation).cmp(&(&b.surface, &b.pronunciation)));
+
+    (
+        [(header::CONTENT_TYPE, query.format.content_type())],
+        dict_format::write(query.format, &rows, count_moras),
+    )
+        .into_response()
+}
+
+/// CSVなどの辞書ファイルから単語を追加する。
+///
+/// 不正な行は飛ばして行ごとのエラーを返し、それ以外の行は取り込む。
+pub async fn import_user_dict_file_post(
+    author: Author,
+    Query(query): Query<ImportUserDictFileQuery>,
+    body: String,
+) -> Result<Json<ImportUserDictFileReport>> {
+    let dry_run = query.dry_run.unwrap_or(false);
+    let mut known: HashSet<(String, String)> = user_dict_words()
+        .await
+        .into_values()
+        .map(|word| (word.surface().to_string(), word.pronunciation().to_string()))
+        .collect();
+
+    let mut report = ImportUserDictFileReport {
+        dry_run,
+        ..Default::default()
+    };
+    let mut words: Vec<UserDictWord> = vec![];
+    for (line, row) in dict_format::parse(query.format, &body, count_moras) {
+        let row = match row {
+            Ok(row) => row,
+            Err(e) => {
+                report.errors.push(RowError {
+                    line,
+                    message: e.to_string(),
+                    fields: vec![],
+                });
+                continue;
+            }
+        };
+        let accent_type = row
+            .accent_type
+            .unwrap_or_else(|| guess_accent_type(count_moras(&to_katakana(&row.pronunciation))));
+        let param = VvUserDictWordParam::new(row.surface, row.pronunciation, accent_type, row.priority.unwrap_or(5))
+            .with_word_type(row.word_type);
+        let param = match param.validate() {
+            Ok(param) => param,
+            Err(Error { message, fields, .. }) => {
+                report.errors.push(RowError { line, message, fields });
+                continue;
+            }
+        };
+        let word: UserDictWord = param.into();
+        if !known.insert((word.surface.clone(), word.pronunciation.clone())) {
+            report.skipped_lines.push(line);
+            continue;
+        }
+        report.accepted_lines.push(line);
+        words.push(word);
+    }
+
+    if !dry_run && !words.is_empty() {
+        report.added = add_user_dict_words(words, "import_file", &author).await?;
+    }
+    Ok(Json(report))
+}
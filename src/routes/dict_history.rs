@@ -0,0 +1,167 @@
+use crate::{
+    dict_history::{self, Change, Entry},
+    models::VvUserDictWord,
+    result::{Error, Result},
+    routes::{
+        user_dict::{replace_user_dict_words, user_dict_history_path},
+        user_dicts::{replace_named_user_dict_words, DEFAULT_USER_DICT_NAME},
+    },
+};
+use std::collections::HashMap;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, Query},
+    Json,
+};
+use http::request::Parts;
+use serde::{Deserialize, Serialize};
+
+/// 履歴に残す変更者。`X-Author`ヘッダーで指定する。
+pub struct Author(pub Option<String>);
+
+static AUTHOR_HEADER: &str = "x-author";
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Author {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+        let author = parts
+            .headers
+            .get(AUTHOR_HEADER)
+            .map(|value| {
+                value
+                    .to_str()
+                    .map(|value| value.trim().to_string())
+                    .map_err(|_| Error::bad_request("X-Authorヘッダーは文字列で指定してください。"))
+            })
+            .transpose()?
+            .filter(|author| !author.is_empty());
+        Ok(Author(author))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictHistoryQuery {
+    /// このIDより古いものを返す。
+    before_id: Option<u64>,
+    limit: Option<usize>,
+    /// 名前付き辞書の履歴だけを返す。省略するか`default`なら`/user_dict`の辞書の履歴を返す。
+    user_dict: Option<String>,
+}
+
+/// 履歴では既定の辞書を`None`で記録しているので、`default`を`None`にそろえる。
+fn history_user_dict(user_dict: Option<String>) -> Option<String> {
+    user_dict.filter(|name| name != DEFAULT_USER_DICT_NAME)
+}
+
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// 新しい順に履歴を返す。
+pub async fn user_dict_history_get(Query(query): Query<UserDictHistoryQuery>) -> Result<Json<Vec<Entry>>> {
+    let user_dict = history_user_dict(query.user_dict);
+    let entries = dict_history::entries(user_dict_history_path())?;
+    Ok(Json(
+        entries
+            .into_iter()
+            .rev()
+            .filter(|entry| entry.user_dict == user_dict)
+            .filter(|entry| query.before_id.is_none_or(|before_id| entry.id < before_id))
+            .take(query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT))
+            .collect(),
+    ))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevertQuery {
+    /// `true`なら、後から変更された単語も変更前に戻す。
+    force: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevertResult {
+    changes: Vec<Change>,
+    /// 後から変更されていた単語のUUID。
+    conflicts: Vec<String>,
+}
+
+/// 指定した変更を打ち消す。打ち消した変更も履歴に残る。
+///
+/// 変更した単語がその後さらに変更されていれば、`force=true`でない限り409を返して何もしない。
+pub async fn user_dict_history_revert_post(
+    author: Author,
+    Path(id): Path<u64>,
+    Query(query): Query<RevertQuery>,
+) -> Result<Json<RevertResult>> {
+    let force = query.force.unwrap_or(false);
+    let entries = dict_history::entries(user_dict_history_path())?;
+    let entry = entries
+        .iter()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| Error::not_found(format!("履歴が見つかりません：{}", id)))?;
+
+    let operation = format!("revert:{}", id);
+    let revert = |words: &mut HashMap<String, VvUserDictWord>| {
+        let conflicts = dict_history::revert(words, &entry.changes, force);
+        if !conflicts.is_empty() && !force {
+            return Err(Error::conflict(format!(
+                "後から変更された単語があります：{}",
+                conflicts.join(", ")
+            )));
+        }
+        Ok(conflicts)
+    };
+    let (conflicts, changes) = match &entry.user_dict {
+        Some(name) => replace_named_user_dict_words(name, &operation, &author, revert).await?,
+        None => replace_user_dict_words(&operation, &author, revert).await?,
+    };
+    Ok(Json(RevertResult { changes, conflicts }))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreHistoryQuery {
+    /// この履歴の直後の状態に戻す。
+    id: Option<u64>,
+    /// この時刻（UNIX時刻、ミリ秒）の状態に戻す。
+    timestamp: Option<u128>,
+    /// 戻す名前付き辞書。省略するか`default`なら`/user_dict`の辞書を戻す。
+    user_dict: Option<String>,
+}
+
+/// 辞書をある時点の状態に戻す。それより後の変更を新しい順に打ち消す。
+pub async fn user_dict_history_restore_post(
+    author: Author,
+    Query(query): Query<RestoreHistoryQuery>,
+) -> Result<Json<RevertResult>> {
+    let user_dict = history_user_dict(query.user_dict);
+    let entries: Vec<Entry> = dict_history::entries(user_dict_history_path())?
+        .into_iter()
+        .filter(|entry| entry.user_dict == user_dict)
+        .collect();
+    let later: Vec<&Entry> = match (query.id, query.timestamp) {
+        (Some(id), None) => {
+            if id != 0 && !entries.iter().any(|entry| entry.id == id) {
+                return Err(Error::not_found(format!("履歴が見つかりません：{}", id)));
+            }
+            entries.iter().filter(|entry| entry.id > id).collect()
+        }
+        (None, Some(timestamp)) => entries.iter().filter(|entry| entry.timestamp > timestamp).collect(),
+        _ => return Err(Error::bad_request("idかtimestampのどちらか一方を指定してください。")),
+    };
+
+    let restore = |words: &mut HashMap<String, VvUserDictWord>| {
+        let mut conflicts = vec![];
+        for entry in later.into_iter().rev() {
+            conflicts.extend(dict_history::revert(words, &entry.changes, true));
+        }
+        conflicts.sort();
+        conflicts.dedup();
+        Ok(conflicts)
+    };
+    let (conflicts, changes) = match &user_dict {
+        Some(name) => replace_named_user_dict_words(name, "restore_history", &author, restore).await?,
+        None => replace_user_dict_words("restore_history", &author, restore).await?,
+    };
+    Ok(Json(RevertResult { changes, conflicts }))
+}
@@ -0,0 +1,83 @@
+use crate::{
+    models::AudioQuery,
+    result::Result,
+    routes::{
+        styles::Style,
+        synthesis::{create_audio_query, synthesize, text_chunks},
+        user_dict::VvUserDictWordParam,
+        user_dicts::{build_user_dicts, select_user_dicts, DEFAULT_USER_DICT_NAME},
+    },
+};
+
+use axum::{extract::Query, Json};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use voicevox_core_rs::UserDictWord;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictWordPreviewQuery {
+    text: String,
+    /// 単語を足す元の辞書。省略すると既定の辞書を使う。
+    user_dict: Option<String>,
+    /// `true`なら音声も合成して返す。
+    synthesis: Option<bool>,
+    enable_interrogative_upspeak: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictWordPreview {
+    before: AudioQuery,
+    after: AudioQuery,
+    /// 読みかアクセントが変わったかどうか。
+    changed: bool,
+    /// `synthesis=true`のときのみ。Base64で符号化したWAV。
+    before_audio: Option<String>,
+    after_audio: Option<String>,
+}
+
+/// 単語を登録する前に、登録したときの読みを確かめる。
+///
+/// 単語の項目は`/user_dict_word`と同じクエリパラメーターで渡す。元の辞書を複製した一時的な辞書に単語を足して解析するので、
+/// 保存されている辞書は変わらない。
+///
+/// 解析には全体で一つのOPEN_JTALKを借りる。解析の間だけ一時的な辞書に差し替え、終われば既定の辞書に戻す。
+/// その間は他のリクエストの解析が待たされる。音声合成器がOPEN_JTALKを持っているため、別のOpenJTalkでは解析できない。
+pub async fn user_dict_word_preview_post(
+    Query(query): Query<UserDictWordPreviewQuery>,
+    Query(param): Query<VvUserDictWordParam>,
+    Style(speaker): Style,
+) -> Result<Json<UserDictWordPreview>> {
+    let word: UserDictWord = param.validate()?.into();
+    text_chunks(&query.text, false, false)?;
+
+    let names = query.user_dict.as_deref().unwrap_or(DEFAULT_USER_DICT_NAME);
+    // UserDictは複製できないので、単語を足す辞書はキャッシュを使わずに作る。
+    let base = select_user_dicts(Some(names)).await?;
+    let scratch = build_user_dicts(names).await?;
+    scratch.0.add_word(word).map_err(anyhow::Error::from)?;
+
+    let before = create_audio_query(&query.text, speaker, false, true, true, base.as_deref()).await?;
+    let after = create_audio_query(&query.text, speaker, false, true, true, Some(&scratch)).await?;
+    let changed = before.kana != after.kana;
+
+    let (before_audio, after_audio) = if query.synthesis.unwrap_or(false) {
+        let enable_interrogative_upspeak = query.enable_interrogative_upspeak.unwrap_or(true);
+        let encode = |wav: Vec<u8>| base64::engine::general_purpose::STANDARD.encode(wav);
+        (
+            Some(encode(
+                synthesize(&before, speaker, enable_interrogative_upspeak).await?,
+            )),
+            Some(encode(synthesize(&after, speaker, enable_interrogative_upspeak).await?)),
+        )
+    } else {
+        (None, None)
+    };
+
+    Ok(Json(UserDictWordPreview {
+        before,
+        after,
+        changed,
+        before_audio,
+        after_audio,
+    }))
+}
@@ -0,0 +1,109 @@
+use crate::{
+    dict_suggest,
+    kana::count_moras,
+    models::AudioQuery,
+    result::{Error, Result},
+    routes::{
+        dict_history::Author,
+        styles::Style,
+        synthesis::create_audio_query,
+        user_dict::{add_user_dict_words, VvUserDictWordParam},
+    },
+};
+
+use axum::{extract::Query, Json};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictSuggestionQuery {
+    text: String,
+    /// 意図した読み。アクセント核の直後に`'`を置ける。
+    reading: String,
+    /// `true`なら候補をそのまま辞書に登録する。
+    apply: Option<bool>,
+    priority: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictSuggestion {
+    word: VvUserDictWordParam,
+    current_reading: Option<String>,
+    /// 登録したときのみ。
+    word_uuid: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictSuggestionResult {
+    suggestions: Vec<UserDictSuggestion>,
+    /// 登録したときのみ、登録前後のAudioQueryを返す。
+    before: Option<AudioQuery>,
+    after: Option<AudioQuery>,
+}
+
+/// 読み間違えたテキストと正しい読みから、ユーザー辞書に登録する単語を提案する。
+///
+/// 読みの対応を取るため、テキストの正規化や英単語の読みの補完は行わずに解析する。
+pub async fn user_dict_suggestions_post(
+    author: Author,
+    Query(query): Query<UserDictSuggestionQuery>,
+    Style(speaker): Style,
+) -> Result<Json<UserDictSuggestionResult>> {
+    let apply = query.apply.unwrap_or(false);
+    let before = create_audio_query(&query.text, speaker, false, false, false, None).await?;
+    let current: String = before
+        .accent_phrases
+        .iter()
+        .flat_map(|accent_phrase| accent_phrase.moras.iter().map(|mora| mora.text.as_str()))
+        .collect();
+    let suggestions = dict_suggest::suggest(&query.text, &query.reading, &current, count_moras)
+        .map_err(|e| Error::bad_request(e.to_string()))?;
+
+    let words: Vec<VvUserDictWordParam> = suggestions
+        .iter()
+        .map(|suggestion| {
+            VvUserDictWordParam::new(
+                suggestion.surface.clone(),
+                suggestion.pronunciation.clone(),
+                suggestion.accent_type,
+                query.priority.unwrap_or(5),
+            )
+        })
+        .collect();
+    // 一語でも登録できなければ何も登録しない。
+    let word_uuids = if apply && !words.is_empty() {
+        let validated = words
+            .iter()
+            .map(|word| Ok(word.clone().validate()?.into()))
+            .collect::<Result<Vec<_>>>()?;
+        add_user_dict_words(validated, "suggest", &author)
+            .await?
+            .into_iter()
+            .map(Some)
+            .collect()
+    } else {
+        vec![None; words.len()]
+    };
+
+    let results = words
+        .into_iter()
+        .zip(suggestions)
+        .zip(word_uuids)
+        .map(|((word, suggestion), word_uuid)| UserDictSuggestion {
+            word,
+            current_reading: suggestion.current_reading,
+            word_uuid,
+        })
+        .collect();
+
+    let (before, after) = if apply {
+        let after = create_audio_query(&query.text, speaker, false, false, false, None).await?;
+        (Some(before), Some(after))
+    } else {
+        (None, None)
+    };
+    Ok(Json(UserDictSuggestionResult {
+        suggestions: results,
+        before,
+        after,
+    }))
+}
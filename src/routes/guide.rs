@@ -0,0 +1,119 @@
+use crate::{
+    guide,
+    models::{AccentPhrase, AudioQuery},
+    result::{Error, Result},
+    routes::{
+        styles::Style,
+        synthesis::{create_audio_query, synthesize},
+    },
+    wav,
+};
+
+use axum::{body::Bytes, extract::Query, Json};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuidedQuery {
+    text: String,
+    is_kana: Option<bool>,
+    normalize: Option<bool>,
+    english: Option<bool>,
+    /// 参照音声の声の高さをスタイルの高さに合わせるか。
+    normalize_pitch: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuidedSynthesisQuery {
+    text: String,
+    is_kana: Option<bool>,
+    normalize: Option<bool>,
+    english: Option<bool>,
+    normalize_pitch: Option<bool>,
+    enable_interrogative_upspeak: Option<bool>,
+}
+
+/// 参照音声（WAV）に合わせて音素長と音高を書き換えたAudioQueryを作る。
+async fn create_guided_audio_query(
+    text: &str,
+    speaker: u32,
+    is_kana: bool,
+    normalize: bool,
+    english: bool,
+    normalize_pitch: bool,
+    reference: &[u8],
+) -> Result<AudioQuery> {
+    let (sample_rate, samples) =
+        wav::samples(reference).map_err(|e| Error::bad_request(format!("参照音声を読めませんでした：{}", e)))?;
+    let duration = samples.len() as f32 / sample_rate.max(1) as f32;
+    if duration > guide::MAX_REFERENCE_DURATION {
+        return Err(Error::payload_too_large(format!(
+            "参照音声が長すぎます（{:.1}秒、上限{}秒）。",
+            duration,
+            guide::MAX_REFERENCE_DURATION
+        )));
+    }
+    let mut audio_query = create_audio_query(text, speaker, is_kana, normalize, english, None).await?;
+    let mora_count: usize = audio_query
+        .accent_phrases
+        .iter()
+        .map(|accent_phrase| accent_phrase.moras.len())
+        .sum();
+    if mora_count > guide::MAX_MORA_COUNT {
+        return Err(Error::payload_too_large(format!(
+            "テキストが長すぎます（{}モーラ、上限{}モーラ）。",
+            mora_count,
+            guide::MAX_MORA_COUNT
+        )));
+    }
+    // 解析と位置合わせは重いので、非同期のスレッドを塞がないよう別のスレッドで行う。
+    tokio::task::spawn_blocking(move || {
+        let frames = guide::analyze(&samples, sample_rate)?;
+        guide::align(&mut audio_query, &frames, normalize_pitch)?;
+        Ok(audio_query)
+    })
+    .await
+    .map_err(anyhow::Error::from)?
+    .map_err(|e: anyhow::Error| Error::bad_request(e.to_string()))
+}
+
+/// リクエストボディは参照音声のWAV。
+pub async fn guided_accent_phrases_post(
+    Query(query): Query<GuidedQuery>,
+    Style(speaker): Style,
+    reference: Bytes,
+) -> Result<Json<Vec<AccentPhrase>>> {
+    let audio_query = create_guided_audio_query(
+        &query.text,
+        speaker,
+        query.is_kana.unwrap_or(false),
+        query.normalize.unwrap_or(true),
+        query.english.unwrap_or(true),
+        query.normalize_pitch.unwrap_or(true),
+        &reference,
+    )
+    .await?;
+    Ok(Json(audio_query.accent_phrases))
+}
+
+pub async fn guided_synthesis_post(
+    Query(query): Query<GuidedSynthesisQuery>,
+    Style(speaker): Style,
+    reference: Bytes,
+) -> Result<Vec<u8>> {
+    let audio_query = create_guided_audio_query(
+        &query.text,
+        speaker,
+        query.is_kana.unwrap_or(false),
+        query.normalize.unwrap_or(true),
+        query.english.unwrap_or(true),
+        query.normalize_pitch.unwrap_or(true),
+        &reference,
+    )
+    .await?;
+    synthesize(
+        &audio_query,
+        speaker,
+        query.enable_interrogative_upspeak.unwrap_or(true),
+    )
+    .await
+}
@@ -0,0 +1,24 @@
+use crate::{
+    kana,
+    models::AccentPhrase,
+    result::{Error, Result},
+};
+
+use axum::{extract::Query, Json};
+use serde::{Deserialize, Serialize};
+
+pub async fn accent_phrases_to_kana_post(Json(accent_phrases): Json<Vec<AccentPhrase>>) -> Json<String> {
+    Json(kana::create_kana(&accent_phrases))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KanaQuery {
+    kana: String,
+}
+
+/// 音高と音素長は0になるので、必要なら`/mora_data`で予測し直す。
+pub async fn kana_to_accent_phrases_post(Query(query): Query<KanaQuery>) -> Result<Json<Vec<AccentPhrase>>> {
+    Ok(Json(
+        kana::parse_kana(&query.kana).map_err(|e| Error::bad_request(e.to_string()))?,
+    ))
+}
@@ -0,0 +1,44 @@
+use crate::{lip_sync, models::AudioQuery, timeline::phoneme_timeline};
+
+use axum::{
+    extract::Query,
+    response::{IntoResponse, Response},
+    Json,
+};
+use http::header;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LipSyncFormat {
+    RhubarbJson,
+    RhubarbTsv,
+    Vmd,
+    Curve,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LipSyncQuery {
+    format: LipSyncFormat,
+    fps: Option<f32>,
+}
+
+pub async fn lip_sync_post(Query(query): Query<LipSyncQuery>, Json(audio_query): Json<AudioQuery>) -> Response {
+    let timeline = phoneme_timeline(&audio_query);
+    let fps = query.fps.filter(|fps| *fps > 0.0).unwrap_or(30.0);
+
+    match query.format {
+        LipSyncFormat::RhubarbJson => Json(lip_sync::rhubarb_json(&timeline)).into_response(),
+        LipSyncFormat::RhubarbTsv => (
+            [(header::CONTENT_TYPE, "text/tab-separated-values; charset=utf-8")],
+            lip_sync::rhubarb_tsv(&timeline),
+        )
+            .into_response(),
+        LipSyncFormat::Vmd => (
+            [(header::CONTENT_TYPE, "application/octet-stream")],
+            lip_sync::vmd(&timeline, fps),
+        )
+            .into_response(),
+        LipSyncFormat::Curve => Json(lip_sync::curve_json(&timeline, fps)).into_response(),
+    }
+}
@@ -1,9 +1,41 @@
+mod accent_edit;
+mod dict_format;
+mod dict_history;
+mod dict_preview;
+mod dict_suggest;
+mod guide;
 mod info;
+mod kana;
+mod lip_sync;
+mod normalize;
+mod pitch_edit;
+mod reading;
+mod script;
 mod speakers;
+mod ssml;
+mod styles;
+mod subtitle;
 mod user_dict;
+mod user_dicts;
 mod synthesis;
 
+pub use accent_edit::*;
+pub use dict_format::*;
+pub use dict_history::*;
+pub use dict_preview::*;
+pub use dict_suggest::*;
+pub use guide::*;
 pub use info::*;
+pub use kana::*;
+pub use lip_sync::*;
+pub use normalize::*;
+pub use pitch_edit::*;
+pub use reading::*;
+pub use script::*;
 pub use speakers::*;
+pub use ssml::*;
+pub use styles::*;
+pub use subtitle::*;
 pub use user_dict::*;
+pub use user_dicts::*;
 pub use synthesis::*;
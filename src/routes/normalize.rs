@@ -0,0 +1,40 @@
+use crate::normalize::{self, Step};
+
+use axum::{extract::Query, Json};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizeQuery {
+    text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizeTrace {
+    step: Step,
+    text: String,
+}
+
+impl NormalizeTrace {
+    pub fn new(step: Step, text: String) -> Self {
+        NormalizeTrace { step, text }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizeResult {
+    text: String,
+    /// テキストが変化した段階のみ。
+    steps: Vec<NormalizeTrace>,
+}
+
+/// 正規化の結果を確認するためのエンドポイント。
+pub async fn normalize_text_post(Query(query): Query<NormalizeQuery>) -> Json<NormalizeResult> {
+    let (text, trace) = normalize::normalize_with_trace(&query.text);
+    Json(NormalizeResult {
+        text,
+        steps: trace
+            .into_iter()
+            .map(|(step, text)| NormalizeTrace::new(step, text))
+            .collect(),
+    })
+}
@@ -0,0 +1,25 @@
+use crate::{
+    models::AudioQuery,
+    pitch_edit::{self, PitchOperation},
+    result::{Error, Result},
+};
+
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PitchEditRequest {
+    audio_query: AudioQuery,
+    /// 先頭から順に適用する。
+    operations: Vec<PitchOperation>,
+}
+
+/// AudioQueryの音高を編集して返す。音高の再予測はしない。
+pub async fn pitch_edit_post(Json(request): Json<PitchEditRequest>) -> Result<Json<AudioQuery>> {
+    let mut audio_query = request.audio_query;
+    for operation in &request.operations {
+        pitch_edit::apply(&mut audio_query, operation).map_err(|e| Error::bad_request(e.to_string()))?;
+    }
+
+    Ok(Json(audio_query))
+}
@@ -0,0 +1,105 @@
+use crate::{
+    english, kana,
+    models::{AccentPhrase, VvUserDictWord},
+    normalize,
+    result::Result,
+    routes::{normalize::NormalizeTrace, styles::Style, synthesis::create_accent_phrases, user_dict::user_dict_words},
+    utils::to_full_width,
+};
+
+use axum::{extract::Query, Json};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingDebugQuery {
+    text: String,
+    normalize: Option<bool>,
+    english: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictMatch {
+    word_uuid: String,
+    word: VvUserDictWord,
+    /// 全角にしたテキスト中の位置（文字数）。
+    /// OpenJTalkが実際にこの単語で区切ったかどうかは分からない。
+    position: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccentPhraseReading {
+    /// AquesTalk風記法での読み。
+    kana: String,
+    accent: u32,
+    mora_count: usize,
+    accent_phrase: AccentPhrase,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingDebug {
+    /// OpenJTalkに渡したテキスト。
+    text: String,
+    normalize_steps: Vec<NormalizeTrace>,
+    user_dict_matches: Vec<UserDictMatch>,
+    accent_phrases: Vec<AccentPhraseReading>,
+}
+
+/// テキストがどう読まれたかを調べるためのエンドポイント。今は前処理の経過と、アクセント句ごとの読みだけを返す。
+///
+/// 求められているのは形態素解析の結果（表記、品詞、読み、アクセント型、連結フラグ）とフルコンテキストラベル、
+/// それにユーザー辞書から来た形態素の印だが、いずれもまだ返せない。voicevox_core-rsの`OpenJtalkRc`は辞書の読み込みしか
+/// 公開しておらず、コアのC APIにも解析結果を取り出す関数が無い。ユーザー辞書の単語も、テキスト中の表記の位置しか分からない。
+// TODO: バインディングが解析結果を公開したら、形態素・ラベル・ユーザー辞書の印を返す。それまでこの要望は未完了。
+pub async fn reading_debug_post(
+    Query(query): Query<ReadingDebugQuery>,
+    Style(speaker): Style,
+) -> Result<Json<ReadingDebug>> {
+    let (normalized, normalize_steps) = if query.normalize.unwrap_or(true) {
+        normalize::normalize_with_trace(&query.text)
+    } else {
+        (query.text.clone(), vec![])
+    };
+
+    let words = user_dict_words().await;
+    let text = if query.english.unwrap_or(true) {
+        let surfaces: HashSet<String> = words.values().map(|word| word.surface().to_string()).collect();
+        english::transliterate(&normalized, &surfaces)
+    } else {
+        normalized
+    };
+
+    let full_width_text = to_full_width(&text);
+    let mut user_dict_matches = vec![];
+    for (word_uuid, word) in &words {
+        for (index, _) in full_width_text.match_indices(word.surface()) {
+            user_dict_matches.push(UserDictMatch {
+                word_uuid: word_uuid.clone(),
+                word: word.clone(),
+                position: full_width_text[..index].chars().count(),
+            });
+        }
+    }
+    user_dict_matches.sort_by_key(|user_dict_match| user_dict_match.position);
+
+    let accent_phrases = create_accent_phrases(&text, speaker, false, false, false, None)
+        .await?
+        .into_iter()
+        .map(|accent_phrase| AccentPhraseReading {
+            kana: kana::create_kana(std::slice::from_ref(&accent_phrase)),
+            accent: accent_phrase.accent,
+            mora_count: accent_phrase.moras.len(),
+            accent_phrase,
+        })
+        .collect();
+
+    Ok(Json(ReadingDebug {
+        text,
+        normalize_steps: normalize_steps
+            .into_iter()
+            .map(|(step, text)| NormalizeTrace::new(step, text))
+            .collect(),
+        user_dict_matches,
+        accent_phrases,
+    }))
+}
@@ -0,0 +1,117 @@
+use crate::{
+    result::{Error, Result},
+    routes::{
+        styles::resolve_style,
+        synthesis::{create_audio_query, synthesize},
+    },
+    vvm_manager::VVM_MANAGER,
+    wav,
+};
+
+use axum::{extract::Query, Json};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptLine {
+    /// スタイルID。`style`と両方指定された場合はこちらを優先する。
+    speaker: Option<u32>,
+    /// `キャラクター名/スタイル名`か`キャラクター名`。
+    style: Option<String>,
+    text: String,
+    speed_scale: Option<f32>,
+    pitch_scale: Option<f32>,
+    intonation_scale: Option<f32>,
+    volume_scale: Option<f32>,
+    pre_phoneme_length: Option<f32>,
+    post_phoneme_length: Option<f32>,
+    /// この行の後に入れる無音（秒）。
+    #[serde(default)]
+    pause_after: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptSynthesisQuery {
+    enable_interrogative_upspeak: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptLineTiming {
+    pub speaker: u32,
+    pub text: String,
+    pub start: f32,
+    pub end: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptSynthesisResult {
+    /// 全ての行を繋げたWAVをBase64にしたもの。
+    pub wav: String,
+    pub lines: Vec<ScriptLineTiming>,
+}
+
+pub async fn script_synthesis_post(
+    Query(query): Query<ScriptSynthesisQuery>,
+    Json(script): Json<Vec<ScriptLine>>,
+) -> Result<Json<ScriptSynthesisResult>> {
+    if script.is_empty() {
+        return Err(Error::bad_request("台本が空です。"));
+    }
+
+    let speakers = {
+        let vvm_manager = VVM_MANAGER.get().unwrap().lock().await;
+        script
+            .iter()
+            .enumerate()
+            .map(|(i, line)| match (line.speaker, &line.style) {
+                (Some(speaker), _) => Ok(speaker),
+                (None, Some(style)) => resolve_style(&vvm_manager, style).map_err(|e| Error {
+                    message: format!("{}行目：{}", i + 1, e.message),
+                    ..e
+                }),
+                (None, None) => Err(Error::bad_request(format!(
+                    "{}行目：speakerかstyleを指定してください。",
+                    i + 1
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut wavs = vec![];
+    let mut gaps = vec![];
+    let mut lines = vec![];
+    let mut cursor = 0.0;
+    for (line, speaker) in script.iter().zip(speakers) {
+        let mut audio_query = create_audio_query(&line.text, speaker, false, true, true, None).await?;
+        audio_query.speed_scale = line.speed_scale.unwrap_or(audio_query.speed_scale);
+        audio_query.pitch_scale = line.pitch_scale.unwrap_or(audio_query.pitch_scale);
+        audio_query.intonation_scale = line.intonation_scale.unwrap_or(audio_query.intonation_scale);
+        audio_query.volume_scale = line.volume_scale.unwrap_or(audio_query.volume_scale);
+        audio_query.pre_phoneme_length = line.pre_phoneme_length.unwrap_or(audio_query.pre_phoneme_length);
+        audio_query.post_phoneme_length = line.post_phoneme_length.unwrap_or(audio_query.post_phoneme_length);
+
+        let audio = synthesize(
+            &audio_query,
+            speaker,
+            query.enable_interrogative_upspeak.unwrap_or(true),
+        )
+        .await?;
+        let duration = wav::duration(&audio)?;
+        lines.push(ScriptLineTiming {
+            speaker,
+            text: line.text.clone(),
+            start: cursor,
+            end: cursor + duration,
+        });
+        cursor += duration + line.pause_after.max(0.0);
+        wavs.push(audio);
+        gaps.push(line.pause_after);
+    }
+
+    let audio = wav::concat(&wavs, &gaps)?;
+
+    Ok(Json(ScriptSynthesisResult {
+        wav: base64::engine::general_purpose::STANDARD.encode(audio),
+        lines,
+    }))
+}
@@ -52,6 +52,7 @@ pub async fn speaker_info_get(Query(query): Query<SpeakerInfoGetQuery>) -> impl
             StatusCode::NOT_FOUND,
             Json(ErrorJson {
                 error: "Speaker not found".to_string(),
+                fields: vec![],
             }),
         )
             .into_response(),
@@ -0,0 +1,129 @@
+use crate::{
+    models::{AudioQuery, Mora},
+    result::{Error, Result},
+    routes::{
+        styles::Style,
+        synthesis::{create_accent_phrases, synthesize},
+    },
+    ssml::{self, Piece},
+    vvm_manager::VVM_MANAGER,
+    wav,
+};
+
+use axum::{extract::Query, Json};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SsmlQuery {
+    enable_interrogative_upspeak: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SsmlAudioQuery {
+    pub speaker: u32,
+    pub audio_query: AudioQuery,
+}
+
+fn pause_mora(length: f32) -> Mora {
+    Mora {
+        text: "、".to_string(),
+        consonant: None,
+        consonant_length: None,
+        vowel: "pau".to_string(),
+        vowel_length: length,
+        pitch: 0.0,
+    }
+}
+
+/// SSMLをスタイルごとのAudioQueryに変換する。
+pub async fn compile_ssml(ssml: &str, default_speaker: u32) -> Result<Vec<SsmlAudioQuery>> {
+    let chunks = {
+        let vvm_manager = VVM_MANAGER.get().unwrap().lock().await;
+        ssml::parse(ssml, default_speaker, |name| vvm_manager.find_style(name))
+            .map_err(|e| Error::bad_request(format!("SSMLを解釈できませんでした：{}", e)))?
+    };
+
+    let mut queries: Vec<SsmlAudioQuery> = vec![];
+    // 読む部分が無い塊の無音は次のクエリの前に足す。
+    let mut carried_silence = 0.0;
+    for chunk in chunks {
+        let speed_scale = chunk.prosody.speed_scale;
+        let mut accent_phrases = vec![];
+        let mut leading_silence = carried_silence;
+        for piece in chunk.pieces {
+            match piece {
+                Piece::Text(text) => {
+                    accent_phrases.extend(create_accent_phrases(&text, chunk.speaker, false, true, true, None).await?);
+                }
+                Piece::Kana(kana) => {
+                    accent_phrases.extend(create_accent_phrases(&kana, chunk.speaker, true, false, false, None).await?);
+                }
+                // 長さは話速で割られるので、指定された秒数になるよう掛けておく。
+                Piece::Break(length) => match accent_phrases.last_mut() {
+                    Some(last) => match &mut last.pause_mora {
+                        Some(pause_mora) => pause_mora.vowel_length += length * speed_scale,
+                        None => last.pause_mora = Some(pause_mora(length * speed_scale)),
+                    },
+                    None => leading_silence += length,
+                },
+            }
+        }
+
+        if accent_phrases.is_empty() {
+            match queries.last_mut() {
+                Some(last) => last.audio_query.post_phoneme_length += leading_silence * last.audio_query.speed_scale,
+                None => carried_silence = leading_silence,
+            }
+            continue;
+        }
+        carried_silence = 0.0;
+
+        queries.push(SsmlAudioQuery {
+            speaker: chunk.speaker,
+            audio_query: AudioQuery {
+                accent_phrases,
+                speed_scale,
+                pitch_scale: chunk.prosody.pitch_scale,
+                intonation_scale: 1.0,
+                volume_scale: chunk.prosody.volume_scale,
+                pre_phoneme_length: 0.1 + leading_silence * speed_scale,
+                post_phoneme_length: 0.1,
+                output_sampling_rate: 24000,
+                output_stereo: false,
+                kana: String::new(),
+            },
+        });
+    }
+
+    Ok(queries)
+}
+
+/// `speaker`は`<voice>`の外側で使うスタイル。
+pub async fn ssml_audio_query_post(Style(speaker): Style, ssml: String) -> Result<Json<Vec<SsmlAudioQuery>>> {
+    Ok(Json(compile_ssml(&ssml, speaker).await?))
+}
+
+pub async fn ssml_synthesis_post(
+    Query(query): Query<SsmlQuery>,
+    Style(speaker): Style,
+    ssml: String,
+) -> Result<Vec<u8>> {
+    let queries = compile_ssml(&ssml, speaker).await?;
+    if queries.is_empty() {
+        return Err(Error::bad_request("読み上げる内容がありません。"));
+    }
+
+    let mut wavs = vec![];
+    for query_item in &queries {
+        wavs.push(
+            synthesize(
+                &query_item.audio_query,
+                query_item.speaker,
+                query.enable_interrogative_upspeak.unwrap_or(true),
+            )
+            .await?,
+        );
+    }
+
+    Ok(wav::concat(&wavs, &[])?)
+}
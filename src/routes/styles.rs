@@ -0,0 +1,111 @@
+use crate::{
+    result::{Error, Result},
+    vvm_manager::{VvmManager, VVM_MANAGER},
+};
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    Json,
+};
+use http::request::Parts;
+use serde::{Deserialize, Serialize};
+
+/// クエリパラメーターから解決したスタイルID。
+///
+/// `speaker`にはスタイルIDの他に`キャラクター名/スタイル名`や`キャラクター名`も指定できる。
+/// `speaker_uuid`と`style_name`の組み合わせでも指定できる。
+pub struct Style(pub u32);
+
+#[derive(Debug, Clone, Deserialize)]
+struct StyleParams {
+    speaker: Option<String>,
+    speaker_uuid: Option<String>,
+    style_name: Option<String>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Style {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let Query(params) = Query::<StyleParams>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| Error::bad_request(e.body_text()))?;
+        let vvm_manager = VVM_MANAGER.get().unwrap().lock().await;
+
+        match (params.speaker, params.speaker_uuid) {
+            (Some(speaker), _) => resolve_style(&vvm_manager, &speaker).map(Style),
+            (None, Some(speaker_uuid)) => vvm_manager
+                .find_style_by_uuid(&speaker_uuid, params.style_name.as_deref())
+                .map(Style)
+                .ok_or_else(|| {
+                    Error::not_found(format!(
+                        "スタイルが見つかりませんでした：{}/{}",
+                        speaker_uuid,
+                        params.style_name.unwrap_or_default()
+                    ))
+                }),
+            (None, None) => Err(Error::bad_request("speakerかspeaker_uuidを指定してください。")),
+        }
+    }
+}
+
+/// スタイルIDか名前からスタイルIDを探す。見つからなければ近い名前を添えて404を返す。
+pub fn resolve_style(vvm_manager: &VvmManager, name: &str) -> Result<u32> {
+    vvm_manager
+        .find_style(name)
+        .ok_or_else(|| style_not_found(vvm_manager, name))
+}
+
+fn style_not_found(vvm_manager: &VvmManager, name: &str) -> Error {
+    let suggestions = vvm_manager.suggest_styles(name, 3);
+    if suggestions.is_empty() {
+        Error::not_found(format!("スタイルが見つかりませんでした：{}", name))
+    } else {
+        Error::not_found(format!(
+            "スタイルが見つかりませんでした：{}。もしかして：{}",
+            name,
+            suggestions.join("、")
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StylesQuery {
+    name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StyleEntry {
+    pub id: u32,
+    /// `キャラクター名/スタイル名`。
+    pub label: String,
+    pub speaker_name: String,
+    pub speaker_uuid: String,
+    pub style_name: String,
+}
+
+/// スタイルの一覧。`name`を指定するとキャラクター名かスタイル名にそれを含むものに絞り込む。
+pub async fn styles_get(Query(query): Query<StylesQuery>) -> Result<Json<Vec<StyleEntry>>> {
+    let vvm_manager = VVM_MANAGER.get().unwrap().lock().await;
+    let styles: Vec<StyleEntry> = vvm_manager
+        .styles()
+        .map(|(speaker, style)| StyleEntry {
+            id: style.id(),
+            label: format!("{}/{}", speaker.name, style.name()),
+            speaker_name: speaker.name.clone(),
+            speaker_uuid: speaker.speaker_uuid.clone(),
+            style_name: style.name().to_string(),
+        })
+        .filter(|entry| match &query.name {
+            Some(name) => entry.label.contains(name.as_str()) || entry.id.to_string() == *name,
+            None => true,
+        })
+        .collect();
+
+    match &query.name {
+        Some(name) if styles.is_empty() => Err(style_not_found(&vvm_manager, name)),
+        _ => Ok(Json(styles)),
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{
+    models::AudioQuery,
+    subtitle::{self, Granularity},
+    timeline,
+};
+
+use axum::{
+    extract::Query,
+    response::{IntoResponse, Response},
+    Json,
+};
+use http::header;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubtitlesQuery {
+    format: SubtitleFormat,
+    granularity: Option<Granularity>,
+    max_line_length: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubtitleSource {
+    text: String,
+    audio_query: AudioQuery,
+}
+
+/// 複数渡された場合は順番に繋げて合成したものとして扱う。
+pub async fn subtitles_post(Query(query): Query<SubtitlesQuery>, Json(sources): Json<Vec<SubtitleSource>>) -> Response {
+    let granularity = query.granularity.unwrap_or(Granularity::Sentence);
+
+    let mut cues = vec![];
+    let mut offset = 0.0;
+    for source in &sources {
+        cues.extend(subtitle::cues(&source.text, &source.audio_query, offset, granularity));
+        offset += timeline::duration(&source.audio_query);
+    }
+    if let Some(max_line_length) = query.max_line_length {
+        for cue in &mut cues {
+            cue.text = subtitle::wrap(&cue.text, max_line_length);
+        }
+    }
+
+    match query.format {
+        SubtitleFormat::Srt => (
+            [(header::CONTENT_TYPE, "application/x-subrip; charset=utf-8")],
+            subtitle::srt(&cues),
+        )
+            .into_response(),
+        SubtitleFormat::Vtt => (
+            [(header::CONTENT_TYPE, "text/vtt; charset=utf-8")],
+            subtitle::webvtt(&cues),
+        )
+            .into_response(),
+    }
+}
@@ -1,143 +1,461 @@
-use crate::{result::Result, routes::synthesis::OPEN_JTALK};
+use crate::{
+    dict_history::{self, Change},
+    dict_index::{DictIndex, MatchMode, Search, SearchField, SearchPage, SortKey, SortOrder},
+    dict_store::{self, Backup},
+    kana::{count_moras, to_katakana, MORA_REGEX},
+    models::{VvUserDictWord, WordType},
+    result::{Error, FieldError, Result},
+    routes::{dict_history::Author, synthesis::OPEN_JTALK},
+    utils::to_full_width,
+};
 
 use axum::{
     extract::{Path, Query},
     Json,
 };
+use http::StatusCode;
 use once_cell::sync::Lazy;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use tokio::sync::Mutex;
 use tracing::warn;
 use voicevox_core_rs::{UserDict, UserDictWord};
 
-pub struct SendSyncUserDict(pub UserDict);
+/// 辞書と、それに合わせて更新する検索用の索引。
+pub struct SendSyncUserDict(pub UserDict, pub DictIndex);
 
 unsafe impl Send for SendSyncUserDict {}
 unsafe impl Sync for SendSyncUserDict {}
-static MORA_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(concat!(
-        "(?:",
-        "[イ][ェ]|[ヴ][ャュョ]|[トド][ゥ]|[テデ][ィャュョ]|[デ][ェ]|[クグ][ヮ]|", // rule_others
-        "[キシチニヒミリギジビピ][ェャュョ]|",                                    // rule_line_i
-        "[ツフヴ][ァ]|[ウスツフヴズ][ィ]|[ウツフヴ][ェォ]|",                      // rule_line_u
-        "[ァ-ヴー]",                                                              // rule_one_mora
-        ")",
-    ))
-    .unwrap()
-});
 
 pub static USER_DICT: Lazy<Arc<Mutex<SendSyncUserDict>>> = Lazy::new(|| {
-    let user_dict = UserDict::new().unwrap();
-    if std::fs::metadata(USER_DICT_PATH).is_ok() && user_dict.load(USER_DICT_PATH).is_err() {
-        warn!("Failed to load user dict from {:?}", USER_DICT_PATH);
-    }
+    let user_dict = dict_store::load(user_dict_path()).unwrap_or_else(|e| {
+        warn!("Failed to recover user dict from {:?}: {}", USER_DICT_PATH, e);
+        UserDict::new().unwrap()
+    });
+
+    let index = DictIndex::new(words_of(&user_dict));
 
-    Arc::new(Mutex::new(SendSyncUserDict(user_dict)))
+    Arc::new(Mutex::new(SendSyncUserDict(user_dict, index)))
 });
 
 static USER_DICT_PATH: &str = "./user_dict.json";
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct VvUserDictWord {
-    priority: u32,
-    accent_type: usize,
-    mora_count: usize,
-    surface: String,
-    pronunciation: String,
+fn user_dict_path() -> &'static std::path::Path {
+    std::path::Path::new(USER_DICT_PATH)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+static USER_DICT_HISTORY_PATH: &str = "./user_dict_history.jsonl";
+
+pub fn user_dict_history_path() -> &'static std::path::Path {
+    std::path::Path::new(USER_DICT_HISTORY_PATH)
+}
+
+/// 変更を履歴に残す。`user_dict`は名前付きの辞書の名前。
+///
+/// 変更は保存した後なので取り消さないが、履歴を書けなかったことはエラーで伝える。
+pub fn record_history(operation: &str, author: &Author, user_dict: Option<&str>, changes: Vec<Change>) -> Result<()> {
+    dict_history::append(
+        user_dict_history_path(),
+        operation,
+        author.0.clone(),
+        user_dict.map(str::to_string),
+        changes,
+    )
+    .map_err(|e| {
+        Error::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("辞書は保存しましたが、履歴を残せませんでした：{:?}", e),
+        )
+    })?;
+    Ok(())
+}
+
+pub const MIN_PRIORITY: u32 = 0;
+pub const MAX_PRIORITY: u32 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VvUserDictWordParam {
     priority: u32,
     accent_type: usize,
     surface: String,
     pronunciation: String,
+    word_type: Option<WordType>,
+}
+
+impl VvUserDictWordParam {
+    pub fn new(surface: String, pronunciation: String, accent_type: usize, priority: u32) -> Self {
+        VvUserDictWordParam {
+            priority,
+            accent_type,
+            surface,
+            pronunciation,
+            word_type: None,
+        }
+    }
+
+    pub fn with_word_type(mut self, word_type: Option<WordType>) -> Self {
+        self.word_type = word_type;
+        self
+    }
+
+    /// 表記を全角に、読みをカタカナにそろえてから検証する。
+    pub fn validate(mut self) -> Result<Self> {
+        let mut errors = vec![];
+
+        self.surface = to_full_width(&self.surface);
+        if self.surface.is_empty() {
+            errors.push(FieldError::new("surface", "表記が空です。"));
+        }
+
+        self.pronunciation = to_katakana(&self.pronunciation);
+        let mora_count = count_moras(&self.pronunciation);
+        if self.pronunciation.is_empty() {
+            errors.push(FieldError::new("pronunciation", "読みが空です。"));
+        } else if !MORA_REGEX.replace_all(&self.pronunciation, "").is_empty() {
+            errors.push(FieldError::new(
+                "pronunciation",
+                format!("読みはカタカナで指定してください：{}", self.pronunciation),
+            ));
+        } else if self.accent_type > mora_count {
+            errors.push(FieldError::new(
+                "accent_type",
+                format!(
+                    "アクセント型はモーラ数（{}）以下で指定してください：{}",
+                    mora_count, self.accent_type
+                ),
+            ));
+        }
+
+        if !(MIN_PRIORITY..=MAX_PRIORITY).contains(&self.priority) {
+            errors.push(FieldError::new(
+                "priority",
+                format!(
+                    "優先度は{}から{}の間で指定してください：{}",
+                    MIN_PRIORITY, MAX_PRIORITY, self.priority
+                ),
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(Error::unprocessable_entity(errors))
+        }
+    }
 }
 
 impl From<VvUserDictWordParam> for UserDictWord {
     fn from(word: VvUserDictWordParam) -> UserDictWord {
         let mut w = UserDictWord::new(&word.surface[..], &word.pronunciation);
         w.accent_type = word.accent_type;
+        w.word_type = word.word_type.unwrap_or_default().into();
         w.priority = word.priority;
         w
     }
 }
 
-impl From<UserDictWord> for VvUserDictWord {
-    fn from(word: UserDictWord) -> VvUserDictWord {
-        VvUserDictWord {
-            priority: word.priority,
-            accent_type: word.accent_type,
-            mora_count: MORA_REGEX.find_iter(&word.pronunciation).count(),
-            surface: word.surface.clone(),
-            pronunciation: word.pronunciation.clone(),
+/// UUIDをキーにした単語をすべて検証する。エラーの項目名は`<UUID>.surface`のようにする。
+pub fn validate_words(words: HashMap<String, VvUserDictWord>) -> Result<HashMap<String, VvUserDictWord>> {
+    let mut validated = HashMap::new();
+    let mut errors = vec![];
+    for (word_uuid, word) in words {
+        let param = VvUserDictWordParam::new(
+            word.surface().to_string(),
+            word.pronunciation().to_string(),
+            word.accent_type(),
+            word.priority(),
+        )
+        .with_word_type(Some(word.word_type()));
+        match param.validate() {
+            Ok(param) => {
+                validated.insert(word_uuid, VvUserDictWord::from(UserDictWord::from(param)));
+            }
+            Err(Error { fields, .. }) => errors.extend(
+                fields
+                    .into_iter()
+                    .map(|field| FieldError::new(format!("{}.{}", word_uuid, field.field), field.message)),
+            ),
         }
     }
-}
-impl From<VvUserDictWord> for UserDictWord {
-    fn from(word: VvUserDictWord) -> UserDictWord {
-        let mut w = UserDictWord::new(&word.surface[..], &word.pronunciation);
-        w.accent_type = word.accent_type;
-        w.priority = word.priority;
-        w
+    if !errors.is_empty() {
+        errors.sort_by(|a, b| a.field.cmp(&b.field));
+        return Err(Error::unprocessable_entity(errors));
     }
+    Ok(validated)
 }
 
-pub async fn user_dict_get() -> Json<HashMap<String, VvUserDictWord>> {
-    let user_dict = USER_DICT.lock().await;
-
-    Json(serde_json::from_str(&serde_json::to_string(&user_dict.0).unwrap()).unwrap())
+/// バックアップを取ってから保存する。
+fn save_user_dict(user_dict: &UserDict) -> anyhow::Result<()> {
+    dict_store::save(user_dict, user_dict_path())
 }
 
-pub async fn import_user_dict_post(Json(payload): Json<HashMap<String, VvUserDictWord>>) -> Result<&'static str> {
+/// UUIDをキーにした単語をコアに読ませて辞書を作る。読めなければ400にする。
+pub fn user_dict_from_words(words: &impl Serialize) -> Result<UserDict> {
     let temp_file = tempfile::NamedTempFile::new().map_err(anyhow::Error::from)?;
 
     let temp_file_writer = std::io::BufWriter::new(temp_file.as_file());
 
-    serde_json::to_writer(temp_file_writer, &payload).map_err(anyhow::Error::from)?;
+    serde_json::to_writer(temp_file_writer, words).map_err(anyhow::Error::from)?;
 
     let temp_file = temp_file.into_temp_path();
 
     tracing::debug!("Importing user dict from {:?}", temp_file);
 
-    let user_dict = USER_DICT.lock().await;
+    let user_dict = UserDict::new().map_err(anyhow::Error::from)?;
+    user_dict
+        .load(&temp_file)
+        .map_err(|e| Error::bad_request(format!("ユーザー辞書を読み込めませんでした：{}", e)))?;
+
+    Ok(user_dict)
+}
+
+pub fn words_of(user_dict: &UserDict) -> HashMap<String, VvUserDictWord> {
+    serde_json::from_str(&serde_json::to_string(user_dict).unwrap()).unwrap()
+}
+
+/// ユーザー辞書の単語をUUIDをキーにして返す。
+pub async fn user_dict_words() -> HashMap<String, VvUserDictWord> {
+    words_of(&USER_DICT.lock().await.0)
+}
 
-    {
-        let temp_user_dict = UserDict::new().map_err(anyhow::Error::from)?;
-        temp_user_dict.load(&temp_file).map_err(anyhow::Error::from)?;
+pub async fn user_dict_get() -> Json<HashMap<String, VvUserDictWord>> {
+    Json(user_dict_words().await)
+}
 
-        user_dict.0.import(&temp_user_dict).map_err(anyhow::Error::from)?;
+pub const DEFAULT_SEARCH_LIMIT: usize = 100;
+pub const MAX_SEARCH_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictSearchQuery {
+    /// 検索する文字列。省略すると絞り込みのみ行う。
+    q: Option<String>,
+    field: Option<SearchField>,
+    #[serde(rename = "match")]
+    match_mode: Option<MatchMode>,
+    word_type: Option<WordType>,
+    min_priority: Option<u32>,
+    max_priority: Option<u32>,
+    sort: Option<SortKey>,
+    order: Option<SortOrder>,
+    /// 前のページの`next_cursor`。
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+/// ユーザー辞書を検索する。辞書全体を返さずにページごとに取得できる。
+pub async fn user_dict_search_get(Query(query): Query<UserDictSearchQuery>) -> Result<Json<SearchPage>> {
+    let search = Search {
+        query: query.q,
+        field: query.field.unwrap_or(SearchField::Both),
+        mode: query.match_mode.unwrap_or(MatchMode::Substring),
+        word_type: query.word_type,
+        min_priority: query.min_priority,
+        max_priority: query.max_priority,
+        sort: query.sort.unwrap_or(SortKey::Surface),
+        order: query.order.unwrap_or(SortOrder::Asc),
+        cursor: query.cursor,
+        limit: query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT),
+    };
+    let page = USER_DICT
+        .lock()
+        .await
+        .1
+        .search(&search)
+        .map_err(|e| Error::bad_request(format!("カーソルが不正です：{}", e)))?;
+    Ok(Json(page))
+}
+
+/// ユーザー辞書に登録されている表記の一覧。
+pub async fn user_dict_surfaces() -> HashSet<String> {
+    user_dict_words()
+        .await
+        .into_values()
+        .map(|word| word.surface().to_string())
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportUserDictQuery {
+    /// `true`なら同じUUIDの単語を上書きする。`false`なら既存の単語を残す。
+    #[serde(rename = "override")]
+    override_existing: Option<bool>,
+    /// `true`なら検証と報告のみ行い、辞書は変更しない。
+    dry_run: Option<bool>,
+}
+
+/// 同じ表記で読みが異なる単語の組。
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SurfaceConflict {
+    surface: String,
+    word_uuid: String,
+    pronunciation: String,
+    existing_word_uuid: String,
+    existing_pronunciation: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportUserDictReport {
+    added: Vec<String>,
+    replaced: Vec<String>,
+    skipped: Vec<String>,
+    surface_conflicts: Vec<SurfaceConflict>,
+    dry_run: bool,
+}
+
+pub async fn import_user_dict_post(
+    author: Author,
+    Query(query): Query<ImportUserDictQuery>,
+    Json(payload): Json<HashMap<String, VvUserDictWord>>,
+) -> Result<Json<ImportUserDictReport>> {
+    let override_existing = query.override_existing.unwrap_or(false);
+    let dry_run = query.dry_run.unwrap_or(false);
+    let payload = validate_words(payload)?;
+    let mut user_dict = USER_DICT.lock().await;
+    let existing = words_of(&user_dict.0);
+
+    let mut report = ImportUserDictReport {
+        dry_run,
+        ..Default::default()
+    };
+    let mut word_uuids: Vec<&String> = payload.keys().collect();
+    word_uuids.sort();
+    let mut imported = HashMap::new();
+    for word_uuid in word_uuids {
+        let word = &payload[word_uuid];
+        if existing.contains_key(word_uuid) {
+            if !override_existing {
+                report.skipped.push(word_uuid.clone());
+                continue;
+            }
+            report.replaced.push(word_uuid.clone());
+        } else {
+            report.added.push(word_uuid.clone());
+        }
+        imported.insert(word_uuid, word);
+
+        let surface = to_full_width(word.surface());
+        for (existing_uuid, existing_word) in &existing {
+            if existing_uuid != word_uuid
+                && existing_word.surface() == surface
+                && existing_word.pronunciation() != word.pronunciation()
+            {
+                report.surface_conflicts.push(SurfaceConflict {
+                    surface: surface.clone(),
+                    word_uuid: word_uuid.clone(),
+                    pronunciation: word.pronunciation().to_string(),
+                    existing_word_uuid: existing_uuid.clone(),
+                    existing_pronunciation: existing_word.pronunciation().to_string(),
+                });
+            }
+        }
     }
 
-    OPEN_JTALK.lock().await.0.use_user_dict(&user_dict.0).unwrap();
+    // 取り込む単語をコアに読ませて検証する。
+    let temp_user_dict = user_dict_from_words(&imported)?;
 
-    Ok("")
+    if dry_run {
+        return Ok(Json(report));
+    }
+
+    let staged = user_dict_from_words(&words_of(&user_dict.0))?;
+    staged.import(&temp_user_dict).map_err(anyhow::Error::from)?;
+    save_user_dict(&staged)?;
+
+    let index = DictIndex::new(words_of(&staged));
+    let recorded = record_history(
+        "import",
+        &author,
+        None,
+        dict_history::diff(user_dict.1.words(), index.words()),
+    );
+    user_dict.1 = index;
+    user_dict.0 = staged;
+
+    OPEN_JTALK
+        .lock()
+        .await
+        .0
+        .use_user_dict(&user_dict.0)
+        .map_err(anyhow::Error::from)?;
+
+    recorded?;
+    Ok(Json(report))
 }
 
-pub async fn user_dict_word_post(Query(param): Query<VvUserDictWordParam>) -> Result<String> {
-    let user_dict = &USER_DICT.lock().await;
+pub async fn user_dict_word_post(author: Author, Query(param): Query<VvUserDictWordParam>) -> Result<String> {
+    add_user_dict_word(param, &author).await
+}
 
-    let word: UserDictWord = param.into();
+/// 単語を追加して保存し、UUIDを返す。
+pub async fn add_user_dict_word(param: VvUserDictWordParam, author: &Author) -> Result<String> {
+    let word: UserDictWord = param.validate()?.into();
 
-    let word_uuid = user_dict.0.add_word(word).map_err(anyhow::Error::from)?;
+    Ok(add_user_dict_words(vec![word], "add", author).await?.remove(0))
+}
 
-    user_dict.0.save(USER_DICT_PATH).map_err(anyhow::Error::from)?;
+/// 検証済みの単語をまとめて追加し、一度だけ保存する。UUIDを追加した順に返す。
+///
+/// 複製した辞書に追加してから置き換えるので、途中で失敗すれば何も追加しない。
+pub async fn add_user_dict_words(words: Vec<UserDictWord>, operation: &str, author: &Author) -> Result<Vec<String>> {
+    let mut user_dict = USER_DICT.lock().await;
+    let staged = user_dict_from_words(&words_of(&user_dict.0))?;
+
+    let mut word_uuids = vec![];
+    for word in words {
+        let word_uuid = staged.add_word(word).map_err(anyhow::Error::from)?;
+        word_uuids.push(word_uuid.hyphenated().to_string());
+    }
 
-    OPEN_JTALK.lock().await.0.use_user_dict(&user_dict.0).unwrap();
+    save_user_dict(&staged)?;
 
-    Ok(word_uuid.hyphenated().to_string())
+    let index = DictIndex::new(words_of(&staged));
+    let recorded = record_history(
+        operation,
+        author,
+        None,
+        dict_history::diff(user_dict.1.words(), index.words()),
+    );
+    user_dict.1 = index;
+    user_dict.0 = staged;
+
+    OPEN_JTALK
+        .lock()
+        .await
+        .0
+        .use_user_dict(&user_dict.0)
+        .map_err(anyhow::Error::from)?;
+
+    recorded?;
+    Ok(word_uuids)
 }
 
-pub async fn user_dict_word_delete(Path(word_uuid): Path<String>) -> Result<&'static str> {
-    let user_dict = &USER_DICT.lock().await;
+pub async fn user_dict_word_delete(author: Author, Path(word_uuid): Path<String>) -> Result<&'static str> {
+    let mut user_dict = USER_DICT.lock().await;
 
     let word_uuid = uuid::Uuid::parse_str(&word_uuid).map_err(anyhow::Error::from)?;
 
-    user_dict.0.remove_word(&word_uuid).map_err(anyhow::Error::from)?;
-
-    user_dict.0.save(USER_DICT_PATH).map_err(anyhow::Error::from)?;
+    let staged = user_dict_from_words(&words_of(&user_dict.0))?;
+    staged.remove_word(&word_uuid).map_err(anyhow::Error::from)?;
+    let word_uuid = word_uuid.hyphenated().to_string();
+    let before = user_dict.1.words().get(&word_uuid).cloned();
+
+    save_user_dict(&staged)?;
+    user_dict.1.remove(&word_uuid);
+    user_dict.0 = staged;
+
+    let recorded = record_history(
+        "delete",
+        &author,
+        None,
+        vec![Change {
+            word_uuid,
+            before,
+            after: None,
+        }],
+    );
 
     OPEN_JTALK
         .lock()
@@ -146,22 +464,80 @@ pub async fn user_dict_word_delete(Path(word_uuid): Path<String>) -> Result<&'st
         .use_user_dict(&user_dict.0)
         .map_err(anyhow::Error::from)?;
 
+    recorded?;
     Ok("")
 }
 
 pub async fn user_dict_word_put(
+    author: Author,
     Path(word_uuid): Path<String>,
     Query(payload): Query<VvUserDictWordParam>,
 ) -> Result<&'static str> {
-    let user_dict = USER_DICT.lock().await;
+    let word: UserDictWord = payload.validate()?.into();
+
+    let mut user_dict = USER_DICT.lock().await;
 
     let word_uuid = uuid::Uuid::parse_str(&word_uuid).map_err(anyhow::Error::from)?;
 
-    let word: UserDictWord = payload.into();
+    let entry = VvUserDictWord::from(&word);
+    let staged = user_dict_from_words(&words_of(&user_dict.0))?;
+    staged.update_word(word_uuid, word).map_err(anyhow::Error::from)?;
+    let word_uuid = word_uuid.hyphenated().to_string();
+    let before = user_dict.1.words().get(&word_uuid).cloned();
+
+    save_user_dict(&staged)?;
+    user_dict.1.insert(word_uuid.clone(), entry.clone());
+    user_dict.0 = staged;
+
+    let recorded = record_history(
+        "update",
+        &author,
+        None,
+        vec![Change {
+            word_uuid,
+            before,
+            after: Some(entry),
+        }],
+    );
+
+    OPEN_JTALK
+        .lock()
+        .await
+        .0
+        .use_user_dict(&user_dict.0)
+        .map_err(anyhow::Error::from)?;
+
+    recorded?;
+    Ok("")
+}
+
+pub async fn user_dict_backups_get() -> Json<Vec<Backup>> {
+    Json(dict_store::backups(user_dict_path()))
+}
+
+/// バックアップからユーザー辞書を戻す。戻す前の辞書もバックアップに残る。
+pub async fn user_dict_backup_restore_post(author: Author, Path(backup_name): Path<String>) -> Result<&'static str> {
+    let mut user_dict = USER_DICT.lock().await;
+
+    let backup_path =
+        dict_store::backup_path(user_dict_path(), &backup_name).map_err(|e| Error::not_found(e.to_string()))?;
 
-    user_dict.0.update_word(word_uuid, word).map_err(anyhow::Error::from)?;
+    let restored = UserDict::new().map_err(anyhow::Error::from)?;
+    restored
+        .load(&backup_path)
+        .map_err(|e| Error::bad_request(format!("バックアップを読み込めませんでした：{}", e)))?;
 
-    user_dict.0.save(USER_DICT_PATH).map_err(anyhow::Error::from)?;
+    save_user_dict(&restored)?;
+
+    let index = DictIndex::new(words_of(&restored));
+    let recorded = record_history(
+        "restore_backup",
+        &author,
+        None,
+        dict_history::diff(user_dict.1.words(), index.words()),
+    );
+    user_dict.1 = index;
+    user_dict.0 = restored;
 
     OPEN_JTALK
         .lock()
@@ -170,5 +546,138 @@ pub async fn user_dict_word_put(
         .use_user_dict(&user_dict.0)
         .map_err(anyhow::Error::from)?;
 
+    recorded?;
     Ok("")
 }
+
+/// 一括操作の一つ。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum UserDictOperation {
+    Add {
+        word: VvUserDictWordParam,
+    },
+    Update {
+        word_uuid: String,
+        word: VvUserDictWordParam,
+    },
+    Delete {
+        word_uuid: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictBatchResult {
+    /// 操作ごとの単語のUUID。追加した単語は新しいUUIDになる。
+    word_uuids: Vec<String>,
+}
+
+/// 追加・更新・削除をまとめて行う。一つでも失敗すれば何も変更しない。
+///
+/// 複製した辞書に順に適用し、すべて成功したときだけ置き換えるので、保存とOpenJTalkへの反映は一度で済む。
+pub async fn user_dict_batch_post(
+    author: Author,
+    Json(operations): Json<Vec<UserDictOperation>>,
+) -> Result<Json<UserDictBatchResult>> {
+    // 単語の内容は辞書に触る前にすべて検証する。
+    let mut errors = vec![];
+    let mut words = vec![];
+    for (i, operation) in operations.iter().enumerate() {
+        let param = match operation {
+            UserDictOperation::Add { word } | UserDictOperation::Update { word, .. } => word.clone(),
+            UserDictOperation::Delete { .. } => {
+                words.push(None);
+                continue;
+            }
+        };
+        match param.validate() {
+            Ok(param) => words.push(Some(UserDictWord::from(param))),
+            Err(Error { fields, .. }) => errors.extend(
+                fields
+                    .into_iter()
+                    .map(|field| FieldError::new(format!("[{}].word.{}", i, field.field), field.message)),
+            ),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(Error::unprocessable_entity(errors));
+    }
+
+    let mut user_dict = USER_DICT.lock().await;
+    let staged = user_dict_from_words(&words_of(&user_dict.0))?;
+
+    let mut word_uuids = vec![];
+    for (i, (operation, word)) in operations.iter().zip(words).enumerate() {
+        let failed = |e: &dyn std::fmt::Display| Error::bad_request(format!("{}番目の操作に失敗しました：{}", i, e));
+        let parse_uuid = |word_uuid: &str| uuid::Uuid::parse_str(word_uuid).map_err(|e| failed(&e));
+        let word_uuid = match operation {
+            UserDictOperation::Add { .. } => staged.add_word(word.unwrap()).map_err(|e| failed(&e))?,
+            UserDictOperation::Update { word_uuid, .. } => {
+                let word_uuid = parse_uuid(word_uuid)?;
+                staged.update_word(word_uuid, word.unwrap()).map_err(|e| failed(&e))?;
+                word_uuid
+            }
+            UserDictOperation::Delete { word_uuid } => {
+                let word_uuid = parse_uuid(word_uuid)?;
+                staged.remove_word(&word_uuid).map_err(|e| failed(&e))?;
+                word_uuid
+            }
+        };
+        word_uuids.push(word_uuid.hyphenated().to_string());
+    }
+
+    save_user_dict(&staged)?;
+
+    let index = DictIndex::new(words_of(&staged));
+    let recorded = record_history(
+        "batch",
+        &author,
+        None,
+        dict_history::diff(user_dict.1.words(), index.words()),
+    );
+    user_dict.1 = index;
+    user_dict.0 = staged;
+
+    OPEN_JTALK
+        .lock()
+        .await
+        .0
+        .use_user_dict(&user_dict.0)
+        .map_err(anyhow::Error::from)?;
+
+    recorded?;
+    Ok(Json(UserDictBatchResult { word_uuids }))
+}
+
+/// 今の単語を`f`で書き換えてから、辞書をまるごと置き換える。履歴から戻すときに使う。UUIDはそのまま残る。
+///
+/// 読んでから置き換えるまでロックを持ち続けるので、その間の他の変更を上書きすることはない。
+/// `f`がエラーを返せば何も変更しない。
+pub async fn replace_user_dict_words<T>(
+    operation: &str,
+    author: &Author,
+    f: impl FnOnce(&mut HashMap<String, VvUserDictWord>) -> Result<T>,
+) -> Result<(T, Vec<Change>)> {
+    let mut user_dict = USER_DICT.lock().await;
+    let mut words = user_dict.1.words().clone();
+    let value = f(&mut words)?;
+    let replaced = user_dict_from_words(&words)?;
+
+    save_user_dict(&replaced)?;
+
+    let index = DictIndex::new(words_of(&replaced));
+    let changes = dict_history::diff(user_dict.1.words(), index.words());
+    let recorded = record_history(operation, author, None, changes.clone());
+    user_dict.1 = index;
+    user_dict.0 = replaced;
+
+    OPEN_JTALK
+        .lock()
+        .await
+        .0
+        .use_user_dict(&user_dict.0)
+        .map_err(anyhow::Error::from)?;
+
+    recorded?;
+    Ok((value, changes))
+}
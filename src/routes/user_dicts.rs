@@ -0,0 +1,310 @@
+use crate::{
+    dict_history::{self, Change},
+    dict_index::DictIndex,
+    dict_store,
+    models::VvUserDictWord,
+    result::{Error, Result},
+    routes::{
+        dict_history::Author,
+        user_dict::{
+            record_history, user_dict_from_words, user_dict_words, validate_words, words_of, SendSyncUserDict,
+            USER_DICT,
+        },
+    },
+};
+
+use axum::{extract::Path, Json};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
+use tokio::sync::Mutex;
+use tracing::warn;
+use voicevox_core_rs::UserDict;
+
+static USER_DICTS_DIR: &str = "./user_dicts";
+
+/// `user_dict=`でこの名前を指定すると、`/user_dict`で扱う既定の辞書を使う。
+pub static DEFAULT_USER_DICT_NAME: &str = "default";
+
+static NAME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("^[A-Za-z0-9_-]{1,64}$").unwrap());
+
+/// 名前付きの辞書。`./user_dicts/<名前>.json`に保存する。
+static NAMED_USER_DICTS: Lazy<Mutex<HashMap<String, SendSyncUserDict>>> = Lazy::new(|| {
+    let mut user_dicts = HashMap::new();
+    let Ok(entries) = std::fs::read_dir(USER_DICTS_DIR) else {
+        return Mutex::new(user_dicts);
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(name) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".json"))
+        else {
+            continue;
+        };
+        if !NAME_REGEX.is_match(name) || name == DEFAULT_USER_DICT_NAME {
+            continue;
+        }
+        match dict_store::load(&path) {
+            Ok(user_dict) => {
+                let index = DictIndex::new(words_of(&user_dict));
+                user_dicts.insert(name.to_string(), SendSyncUserDict(user_dict, index));
+            }
+            Err(e) => warn!("Failed to load user dict {:?}: {}", path, e),
+        }
+    }
+    Mutex::new(user_dicts)
+});
+
+/// リクエストごとに選んだ辞書をまとめたもの。
+pub struct SelectedUserDict(pub UserDict);
+
+unsafe impl Send for SelectedUserDict {}
+unsafe impl Sync for SelectedUserDict {}
+
+/// キャッシュしたまとめた辞書と、作ったときの各辞書の版。
+struct CachedUserDict {
+    versions: Vec<u64>,
+    user_dict: Arc<SelectedUserDict>,
+}
+
+/// 名前の並びをキーにしたキャッシュ。
+static SELECTED_USER_DICTS: Lazy<Mutex<HashMap<String, CachedUserDict>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// キャッシュしておく組み合わせの数。超えたら捨てて作り直す。
+static MAX_SELECTED_USER_DICTS: usize = 16;
+
+fn user_dict_path(name: &str) -> PathBuf {
+    PathBuf::from(USER_DICTS_DIR).join(format!("{}.json", name))
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if !NAME_REGEX.is_match(name) {
+        return Err(Error::bad_request(format!(
+            "辞書の名前は英数字と-_の64文字以内で指定してください：{}",
+            name
+        )));
+    }
+    if name == DEFAULT_USER_DICT_NAME {
+        return Err(Error::bad_request(format!(
+            "{}は既定の辞書の名前です。/user_dictを使ってください。",
+            DEFAULT_USER_DICT_NAME
+        )));
+    }
+    Ok(())
+}
+
+fn split_names(names: &str) -> Vec<&str> {
+    names.split(',').map(|name| name.trim()).collect()
+}
+
+/// 各辞書の今の版。無い辞書があれば404にする。
+async fn versions(names: &[&str]) -> Result<Vec<u64>> {
+    let mut versions = vec![];
+    for name in names {
+        let version = if *name == DEFAULT_USER_DICT_NAME {
+            USER_DICT.lock().await.1.version()
+        } else {
+            let user_dicts = NAMED_USER_DICTS.lock().await;
+            let Some(user_dict) = user_dicts.get(*name) else {
+                return Err(Error::not_found(format!("辞書が見つかりません：{}", name)));
+            };
+            user_dict.1.version()
+        };
+        versions.push(version);
+    }
+    Ok(versions)
+}
+
+/// `user_dict=a,b`の辞書をまとめる。同じ表記の単語が複数の辞書にあるときは、先に指定した辞書のものだけを使う。
+/// 指定が無ければ`None`を返し、既定の辞書を使う。
+///
+/// まとめた辞書はキャッシュし、どれかの辞書が変わるまで使い回す。
+pub async fn select_user_dicts(names: Option<&str>) -> Result<Option<Arc<SelectedUserDict>>> {
+    let Some(names) = names.filter(|names| !names.is_empty()) else {
+        return Ok(None);
+    };
+    let names = split_names(names);
+    let key = names.join(",");
+    // 版を先に読むので、作っている間に辞書が変わっても古い中身を新しい版で覚えることはない。
+    let versions = versions(&names).await?;
+
+    let mut cache = SELECTED_USER_DICTS.lock().await;
+    if let Some(cached) = cache.get(&key).filter(|cached| cached.versions == versions) {
+        return Ok(Some(cached.user_dict.clone()));
+    }
+    let user_dict = Arc::new(build_user_dicts(&key).await?);
+    if cache.len() >= MAX_SELECTED_USER_DICTS && !cache.contains_key(&key) {
+        cache.clear();
+    }
+    cache.insert(
+        key,
+        CachedUserDict {
+            versions,
+            user_dict: user_dict.clone(),
+        },
+    );
+    Ok(Some(user_dict))
+}
+
+/// `select_user_dicts`と同じように辞書をまとめる。キャッシュは使わないので、単語を足しても他に影響しない。
+pub async fn build_user_dicts(names: &str) -> Result<SelectedUserDict> {
+    let mut surfaces = HashSet::new();
+    let mut selected = HashMap::new();
+    for name in split_names(names) {
+        let words = if name == DEFAULT_USER_DICT_NAME {
+            user_dict_words().await
+        } else {
+            let user_dicts = NAMED_USER_DICTS.lock().await;
+            let Some(user_dict) = user_dicts.get(name) else {
+                return Err(Error::not_found(format!("辞書が見つかりません：{}", name)));
+            };
+            words_of(&user_dict.0)
+        };
+        let mut words: Vec<(String, VvUserDictWord)> = words.into_iter().collect();
+        words.sort_by(|a, b| a.0.cmp(&b.0));
+        let new_surfaces: HashSet<String> = words
+            .iter()
+            .map(|(_, word)| word.surface().to_string())
+            .filter(|surface| !surfaces.contains(surface))
+            .collect();
+        for (word_uuid, word) in words {
+            if new_surfaces.contains(word.surface()) {
+                selected.insert(word_uuid, word);
+            }
+        }
+        surfaces.extend(new_surfaces);
+    }
+    Ok(SelectedUserDict(user_dict_from_words(&selected)?))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictInfo {
+    name: String,
+    word_count: usize,
+}
+
+pub async fn user_dicts_get() -> Json<Vec<UserDictInfo>> {
+    let mut infos = vec![UserDictInfo {
+        name: DEFAULT_USER_DICT_NAME.to_string(),
+        word_count: user_dict_words().await.len(),
+    }];
+    let user_dicts = NAMED_USER_DICTS.lock().await;
+    let mut names: Vec<&String> = user_dicts.keys().collect();
+    names.sort();
+    for name in names {
+        infos.push(UserDictInfo {
+            name: name.clone(),
+            word_count: words_of(&user_dicts[name].0).len(),
+        });
+    }
+    Json(infos)
+}
+
+pub async fn named_user_dict_get(Path(name): Path<String>) -> Result<Json<HashMap<String, VvUserDictWord>>> {
+    if name == DEFAULT_USER_DICT_NAME {
+        return Ok(Json(user_dict_words().await));
+    }
+    let user_dicts = NAMED_USER_DICTS.lock().await;
+    let user_dict = user_dicts
+        .get(&name)
+        .ok_or_else(|| Error::not_found(format!("辞書が見つかりません：{}", name)))?;
+    Ok(Json(words_of(&user_dict.0)))
+}
+
+/// 辞書を作る。単語を渡せばそれを入れて作る。
+pub async fn named_user_dict_post(
+    author: Author,
+    Path(name): Path<String>,
+    words: Option<Json<HashMap<String, VvUserDictWord>>>,
+) -> Result<&'static str> {
+    validate_name(&name)?;
+    let mut user_dicts = NAMED_USER_DICTS.lock().await;
+    if user_dicts.contains_key(&name) {
+        return Err(Error::conflict(format!("辞書が既にあります：{}", name)));
+    }
+    let words = validate_words(words.map(|Json(words)| words).unwrap_or_default())?;
+    let user_dict = user_dict_from_words(&words)?;
+
+    std::fs::create_dir_all(USER_DICTS_DIR).map_err(anyhow::Error::from)?;
+    dict_store::save(&user_dict, &user_dict_path(&name))?;
+
+    let index = DictIndex::new(words_of(&user_dict));
+    let changes = dict_history::diff(&HashMap::new(), index.words());
+    let recorded = record_history("create_dict", &author, Some(&name), changes);
+    user_dicts.insert(name, SendSyncUserDict(user_dict, index));
+    recorded?;
+    Ok("")
+}
+
+/// 辞書の中身を置き換える。無ければ作る。
+pub async fn named_user_dict_put(
+    author: Author,
+    Path(name): Path<String>,
+    Json(words): Json<HashMap<String, VvUserDictWord>>,
+) -> Result<&'static str> {
+    validate_name(&name)?;
+    let words = validate_words(words)?;
+    let mut user_dicts = NAMED_USER_DICTS.lock().await;
+    let user_dict = user_dict_from_words(&words)?;
+
+    std::fs::create_dir_all(USER_DICTS_DIR).map_err(anyhow::Error::from)?;
+    dict_store::save(&user_dict, &user_dict_path(&name))?;
+
+    let index = DictIndex::new(words_of(&user_dict));
+    let changes = match user_dicts.get(&name) {
+        Some(before) => dict_history::diff(before.1.words(), index.words()),
+        None => dict_history::diff(&HashMap::new(), index.words()),
+    };
+    let recorded = record_history("replace_dict", &author, Some(&name), changes);
+    user_dicts.insert(name, SendSyncUserDict(user_dict, index));
+    recorded?;
+    Ok("")
+}
+
+/// 辞書を消す。バックアップは残す。消した単語は履歴に残るので、打ち消せば辞書ごと戻る。
+pub async fn named_user_dict_delete(author: Author, Path(name): Path<String>) -> Result<&'static str> {
+    validate_name(&name)?;
+    let mut user_dicts = NAMED_USER_DICTS.lock().await;
+    let Some(removed) = user_dicts.remove(&name) else {
+        return Err(Error::not_found(format!("辞書が見つかりません：{}", name)));
+    };
+    dict_store::remove(&user_dict_path(&name))?;
+    let changes = dict_history::diff(removed.1.words(), &HashMap::new());
+    record_history("delete_dict", &author, Some(&name), changes)?;
+    Ok("")
+}
+
+/// `replace_user_dict_words`の名前付き辞書版。辞書が消えていれば作り直す。
+pub async fn replace_named_user_dict_words<T>(
+    name: &str,
+    operation: &str,
+    author: &Author,
+    f: impl FnOnce(&mut HashMap<String, VvUserDictWord>) -> Result<T>,
+) -> Result<(T, Vec<Change>)> {
+    validate_name(name)?;
+    let mut user_dicts = NAMED_USER_DICTS.lock().await;
+    let before = user_dicts
+        .get(name)
+        .map(|user_dict| user_dict.1.words().clone())
+        .unwrap_or_default();
+    let mut words = before.clone();
+    let value = f(&mut words)?;
+    let user_dict = user_dict_from_words(&words)?;
+
+    std::fs::create_dir_all(USER_DICTS_DIR).map_err(anyhow::Error::from)?;
+    dict_store::save(&user_dict, &user_dict_path(name))?;
+
+    let index = DictIndex::new(words_of(&user_dict));
+    let changes = dict_history::diff(&before, index.words());
+    let recorded = record_history(operation, author, Some(name), changes.clone());
+    user_dicts.insert(name.to_string(), SendSyncUserDict(user_dict, index));
+    recorded?;
+    Ok((value, changes))
+}
@@ -0,0 +1,320 @@
+use crate::normalize::{spell_out, DIGIT_READINGS};
+use anyhow::{anyhow, bail, Context};
+use roxmltree::Node;
+
+/// `<prosody>`で変えられるAudioQueryのパラメーター。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prosody {
+    pub speed_scale: f32,
+    pub pitch_scale: f32,
+    pub volume_scale: f32,
+}
+
+impl Default for Prosody {
+    fn default() -> Self {
+        Prosody {
+            speed_scale: 1.0,
+            pitch_scale: 0.0,
+            volume_scale: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    Text(String),
+    /// AquesTalk風記法の読み。
+    Kana(String),
+    /// 無音（秒）。
+    Break(f32),
+}
+
+/// 同じスタイル・同じ韻律で読む範囲。一つのAudioQueryになる。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub speaker: u32,
+    pub prosody: Prosody,
+    pub pieces: Vec<Piece>,
+}
+
+#[derive(Clone, Copy)]
+struct Scope {
+    speaker: u32,
+    prosody: Prosody,
+}
+
+/// SSMLを読み、スタイルと韻律ごとの塊に分ける。
+///
+/// `resolve_voice`は`<voice name="...">`の名前をスタイルIDに変換する。
+pub fn parse(
+    ssml: &str,
+    default_speaker: u32,
+    resolve_voice: impl Fn(&str) -> Option<u32>,
+) -> anyhow::Result<Vec<Chunk>> {
+    let document = roxmltree::Document::parse(ssml)?;
+    let root = document.root_element();
+    if root.tag_name().name() != "speak" {
+        bail!("ルート要素は<speak>である必要があります。");
+    }
+
+    let mut chunks = vec![];
+    walk(
+        root,
+        Scope {
+            speaker: default_speaker,
+            prosody: Prosody::default(),
+        },
+        &resolve_voice,
+        &mut chunks,
+    )?;
+    Ok(chunks)
+}
+
+fn push(chunks: &mut Vec<Chunk>, scope: Scope, piece: Piece) {
+    if let Piece::Text(text) = &piece {
+        if text.is_empty() {
+            return;
+        }
+    }
+    let mergeable = match (chunks.last(), &piece) {
+        (Some(_), Piece::Break(_)) => true,
+        (Some(last), _) => last.speaker == scope.speaker && last.prosody == scope.prosody,
+        (None, _) => false,
+    };
+    if !mergeable {
+        chunks.push(Chunk {
+            speaker: scope.speaker,
+            prosody: scope.prosody,
+            pieces: vec![],
+        });
+    }
+    let pieces = &mut chunks.last_mut().unwrap().pieces;
+    match (pieces.last_mut(), piece) {
+        (Some(Piece::Text(last)), Piece::Text(text)) => last.push_str(&text),
+        (Some(Piece::Break(last)), Piece::Break(length)) => *last += length,
+        (_, piece) => pieces.push(piece),
+    }
+}
+
+fn walk(
+    node: Node,
+    scope: Scope,
+    resolve_voice: &impl Fn(&str) -> Option<u32>,
+    chunks: &mut Vec<Chunk>,
+) -> anyhow::Result<()> {
+    for child in node.children() {
+        if child.is_text() {
+            let text = child
+                .text()
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            push(chunks, scope, Piece::Text(text));
+            continue;
+        }
+        if !child.is_element() {
+            continue;
+        }
+        match child.tag_name().name() {
+            "break" => push(chunks, scope, Piece::Break(break_length(child)?)),
+            "p" | "s" => {
+                walk(child, scope, resolve_voice, chunks)?;
+                let strength = if child.tag_name().name() == "p" {
+                    "x-strong"
+                } else {
+                    "strong"
+                };
+                push(chunks, scope, Piece::Break(strength_length(strength)?));
+            }
+            "prosody" => {
+                let mut prosody = scope.prosody;
+                if let Some(rate) = child.attribute("rate") {
+                    prosody.speed_scale *= parse_rate(rate)?;
+                }
+                if let Some(pitch) = child.attribute("pitch") {
+                    prosody.pitch_scale += parse_pitch(pitch)?;
+                }
+                if let Some(volume) = child.attribute("volume") {
+                    prosody.volume_scale *= parse_volume(volume)?;
+                }
+                walk(child, Scope { prosody, ..scope }, resolve_voice, chunks)?;
+            }
+            "voice" => {
+                let name = child.attribute("name").context("<voice>にはnameが必要です。")?;
+                let speaker = resolve_voice(name).ok_or_else(|| anyhow!("スタイルが見つかりませんでした：{}", name))?;
+                walk(child, Scope { speaker, ..scope }, resolve_voice, chunks)?;
+            }
+            "say-as" => {
+                let text = text_content(child);
+                let text = match child.attribute("interpret-as") {
+                    Some("characters" | "spell-out") => spell_out(&text),
+                    Some("digits") => read_digits(&text),
+                    _ => text,
+                };
+                push(chunks, scope, Piece::Text(text));
+            }
+            "sub" => {
+                let alias = child.attribute("alias").context("<sub>にはaliasが必要です。")?;
+                push(chunks, scope, Piece::Text(alias.to_string()));
+            }
+            "phoneme" => {
+                let ph = child.attribute("ph").context("<phoneme>にはphが必要です。")?;
+                match child.attribute("alphabet") {
+                    Some("x-aquestalk") => push(chunks, scope, Piece::Kana(ph.to_string())),
+                    Some("x-katakana" | "yomigana") => push(chunks, scope, Piece::Text(ph.to_string())),
+                    // IPAなどには対応していないので、中身をそのまま読む。
+                    _ => walk(child, scope, resolve_voice, chunks)?,
+                }
+            }
+            "mark" | "desc" | "meta" | "metadata" => {}
+            _ => walk(child, scope, resolve_voice, chunks)?,
+        }
+    }
+    Ok(())
+}
+
+fn text_content(node: Node) -> String {
+    node.descendants()
+        .filter(|node| node.is_text())
+        .filter_map(|node| node.text())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn read_digits(text: &str) -> String {
+    text.chars()
+        .map(|c| match c.to_digit(10) {
+            Some(digit) => DIGIT_READINGS[digit as usize].to_string(),
+            None => c.to_string(),
+        })
+        .collect()
+}
+
+fn break_length(node: Node) -> anyhow::Result<f32> {
+    if let Some(time) = node.attribute("time") {
+        return parse_time(time);
+    }
+    strength_length(node.attribute("strength").unwrap_or("medium"))
+}
+
+fn strength_length(strength: &str) -> anyhow::Result<f32> {
+    Ok(match strength {
+        "none" => 0.0,
+        "x-weak" => 0.1,
+        "weak" => 0.2,
+        "medium" => 0.4,
+        "strong" => 0.7,
+        "x-strong" => 1.0,
+        _ => bail!("不明なstrengthです：{}", strength),
+    })
+}
+
+fn parse_time(time: &str) -> anyhow::Result<f32> {
+    let time = time.trim();
+    let (value, scale) = if let Some(value) = time.strip_suffix("ms") {
+        (value, 0.001)
+    } else if let Some(value) = time.strip_suffix('s') {
+        (value, 1.0)
+    } else {
+        bail!("時間の形式が不正です：{}", time);
+    };
+    let value: f32 = value
+        .trim()
+        .parse()
+        .with_context(|| format!("時間の形式が不正です：{}", time))?;
+    Ok((value * scale).max(0.0))
+}
+
+/// `50%`は既定値に対する割合、`+10%`や`-10%`は相対的な変化として倍率にする。
+fn parse_percent(value: &str) -> Option<f32> {
+    let number: f32 = value.strip_suffix('%')?.parse().ok()?;
+    if value.starts_with(['+', '-']) {
+        Some(1.0 + number / 100.0)
+    } else {
+        Some(number / 100.0)
+    }
+}
+
+fn parse_rate(rate: &str) -> anyhow::Result<f32> {
+    let rate = rate.trim();
+    let scale = match rate {
+        "x-slow" => 0.5,
+        "slow" => 0.75,
+        "medium" | "default" => 1.0,
+        "fast" => 1.25,
+        "x-fast" => 1.5,
+        _ => parse_percent(rate)
+            .or_else(|| rate.parse().ok())
+            .ok_or_else(|| anyhow!("rateの形式が不正です：{}", rate))?,
+    };
+    if scale <= 0.0 {
+        bail!("rateは正の値である必要があります：{}", rate);
+    }
+    Ok(scale)
+}
+
+/// `pitch_scale`を半音に換算するときの基準の対数F0（約330Hz）。
+static REFERENCE_LOG_F0: f32 = 5.8;
+
+/// 対数F0を`log_f0_shift`だけ動かす`pitch_scale`。
+///
+/// コアは各モーラの対数F0に`2^pitch_scale`を掛けるので、動く量は元の高さによる。
+/// ここでは`REFERENCE_LOG_F0`の高さで`log_f0_shift`だけ動くように換算する。
+fn pitch_scale_for(log_f0_shift: f32) -> anyhow::Result<f32> {
+    let factor = 1.0 + log_f0_shift / REFERENCE_LOG_F0;
+    if factor <= 0.0 {
+        bail!("pitchが低すぎます。");
+    }
+    Ok(factor.log2())
+}
+
+/// 半音や割合で指定された高さを`pitch_scale`に直す。
+fn parse_pitch(pitch: &str) -> anyhow::Result<f32> {
+    let pitch = pitch.trim();
+    let semitones = |semitones: f32| pitch_scale_for(semitones * std::f32::consts::LN_2 / 12.0);
+    match pitch {
+        "x-low" => semitones(-4.0),
+        "low" => semitones(-2.0),
+        "medium" | "default" => Ok(0.0),
+        "high" => semitones(2.0),
+        "x-high" => semitones(4.0),
+        _ => {
+            if let Some(value) = pitch.strip_suffix("st") {
+                let value: f32 = value
+                    .parse()
+                    .with_context(|| format!("pitchの形式が不正です：{}", pitch))?;
+                semitones(value)
+            } else if let Some(ratio) = parse_percent(pitch).filter(|ratio| *ratio > 0.0) {
+                pitch_scale_for(ratio.ln())
+            } else {
+                bail!("pitchの形式が不正です：{}", pitch);
+            }
+        }
+    }
+}
+
+fn parse_volume(volume: &str) -> anyhow::Result<f32> {
+    let volume = volume.trim();
+    Ok(match volume {
+        "silent" => 0.0,
+        "x-soft" => 0.5,
+        "soft" => 0.75,
+        "medium" | "default" => 1.0,
+        "loud" => 1.25,
+        "x-loud" => 1.5,
+        _ => {
+            if let Some(decibels) = volume.strip_suffix("dB") {
+                let decibels: f32 = decibels
+                    .parse()
+                    .with_context(|| format!("volumeの形式が不正です：{}", volume))?;
+                10f32.powf(decibels / 20.0)
+            } else {
+                parse_percent(volume)
+                    .filter(|scale| *scale >= 0.0)
+                    .ok_or_else(|| anyhow!("volumeの形式が不正です：{}", volume))?
+            }
+        }
+    })
+}
@@ -0,0 +1,234 @@
+use crate::{models::AudioQuery, timeline::phoneme_timeline};
+use serde::{Deserialize, Serialize};
+
+/// OpenJTalkが無音を入れる句読点。
+static CLAUSE_ENDS: &[char] = &['、', '。', '，', '．', ',', '.', '！', '？', '!', '?'];
+static SENTENCE_ENDS: &[char] = &['。', '．', '.', '！', '？', '!', '?'];
+/// 行頭に来てはいけない文字。
+static NO_BREAK_BEFORE: &[char] = &[
+    '、', '。', '，', '．', ',', '.', '！', '？', '!', '?', '」', '』', '）', ')', 'ー', '…',
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f32,
+    pub end: f32,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    Sentence,
+    AccentPhrase,
+}
+
+/// 句読点でテキストを区切る。句読点は直前の節に含める。
+fn split_clauses(text: &str) -> Vec<String> {
+    let mut clauses = vec![];
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        current.push(c);
+        if CLAUSE_ENDS.contains(&c) {
+            while let Some(&next) = chars.peek() {
+                if !NO_BREAK_BEFORE.contains(&next) {
+                    break;
+                }
+                current.push(next);
+                chars.next();
+            }
+            clauses.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        clauses.push(current);
+    }
+    clauses
+}
+
+struct PhraseTiming {
+    start: f32,
+    end: f32,
+    reading: String,
+    mora_count: usize,
+    has_pause: bool,
+}
+
+fn phrase_timings(query: &AudioQuery, offset: f32) -> Vec<PhraseTiming> {
+    let timeline = phoneme_timeline(query);
+    query
+        .accent_phrases
+        .iter()
+        .enumerate()
+        .map(|(i, accent_phrase)| {
+            let spans = timeline
+                .iter()
+                .filter(|span| span.accent_phrase == Some(i) && span.phoneme != "pau");
+            let (start, end) = spans.fold((f32::MAX, f32::MIN), |(start, end), span| {
+                (start.min(span.start), end.max(span.end))
+            });
+            PhraseTiming {
+                start: offset + start,
+                end: offset + end,
+                reading: accent_phrase.moras.iter().map(|mora| mora.text.as_str()).collect(),
+                mora_count: accent_phrase.moras.len(),
+                has_pause: accent_phrase.pause_mora.is_some(),
+            }
+        })
+        .collect()
+}
+
+/// 節の文字列をアクセント句のモーラ数の比で分ける。あくまで近似。
+fn distribute(text: &str, phrases: &[&PhraseTiming]) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let total_moras: usize = phrases.iter().map(|phrase| phrase.mora_count).sum::<usize>().max(1);
+    let mut parts = vec![];
+    let mut consumed_moras = 0;
+    let mut cursor = 0;
+    for (i, phrase) in phrases.iter().enumerate() {
+        consumed_moras += phrase.mora_count;
+        let mut end = if i == phrases.len() - 1 {
+            chars.len()
+        } else {
+            (chars.len() * consumed_moras / total_moras).max(cursor)
+        };
+        while end < chars.len() && NO_BREAK_BEFORE.contains(&chars[end]) {
+            end += 1;
+        }
+        parts.push(chars[cursor..end].iter().collect());
+        cursor = end;
+    }
+    parts
+}
+
+/// 一つのAudioQueryと元のテキストから字幕を作る。`offset`秒ずらして配置する。
+///
+/// テキストの句読点の数とAudioQueryの無音の数が合わない場合は読みを字幕にする。
+pub fn cues(text: &str, query: &AudioQuery, offset: f32, granularity: Granularity) -> Vec<Cue> {
+    let phrases = phrase_timings(query, offset);
+    let mut groups: Vec<Vec<&PhraseTiming>> = vec![];
+    let mut current = vec![];
+    for phrase in &phrases {
+        current.push(phrase);
+        if phrase.has_pause {
+            groups.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+
+    let clauses = split_clauses(text);
+    let clauses: Vec<Option<String>> = if clauses.len() == groups.len() {
+        clauses.into_iter().map(Some).collect()
+    } else if groups.len() == 1 {
+        vec![Some(clauses.concat())]
+    } else {
+        vec![None; groups.len()]
+    };
+
+    match granularity {
+        Granularity::AccentPhrase => groups
+            .iter()
+            .zip(clauses)
+            .flat_map(|(group, clause)| {
+                let texts = match clause {
+                    Some(clause) => distribute(&clause, group),
+                    None => group.iter().map(|phrase| phrase.reading.clone()).collect(),
+                };
+                group
+                    .iter()
+                    .zip(texts)
+                    .map(|(phrase, text)| Cue {
+                        start: phrase.start,
+                        end: phrase.end,
+                        text,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|cue| !cue.text.is_empty())
+            .collect(),
+        Granularity::Sentence => {
+            let mut cues: Vec<Cue> = vec![];
+            let mut open = false;
+            for (group, clause) in groups.iter().zip(clauses) {
+                let text =
+                    clause.unwrap_or_else(|| group.iter().map(|phrase| phrase.reading.as_str()).collect::<String>());
+                let start = group.first().map_or(offset, |phrase| phrase.start);
+                let end = group.last().map_or(offset, |phrase| phrase.end);
+                let sentence_end = text.trim_end_matches(['」', '』', '）', ')']).ends_with(SENTENCE_ENDS);
+                match cues.last_mut() {
+                    Some(last) if open => {
+                        last.end = end;
+                        last.text.push_str(&text);
+                    }
+                    _ => cues.push(Cue { start, end, text }),
+                }
+                open = !sentence_end;
+            }
+            cues
+        }
+    }
+}
+
+/// `max_length`文字ごとに改行する。句読点や閉じ括弧は行頭に来ないよう前の行に寄せる。
+pub fn wrap(text: &str, max_length: usize) -> String {
+    if max_length == 0 {
+        return text.to_string();
+    }
+    let mut lines: Vec<String> = vec![];
+    let mut current = String::new();
+    for c in text.chars() {
+        if current.chars().count() >= max_length && !NO_BREAK_BEFORE.contains(&c) {
+            lines.push(std::mem::take(&mut current));
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines.join("\n")
+}
+
+fn timestamp(seconds: f32, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+pub fn srt(cues: &[Cue]) -> String {
+    let mut srt = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        srt.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            timestamp(cue.start, ','),
+            timestamp(cue.end, ','),
+            cue.text
+        ));
+    }
+    srt
+}
+
+pub fn webvtt(cues: &[Cue]) -> String {
+    let mut vtt = "WEBVTT\n\n".to_string();
+    for cue in cues {
+        vtt.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            timestamp(cue.start, '.'),
+            timestamp(cue.end, '.'),
+            cue.text
+        ));
+    }
+    vtt
+}
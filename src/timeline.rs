@@ -0,0 +1,56 @@
+use crate::models::{AudioQuery, Mora};
+
+/// 音声中の音素一つ分の区間。時間は秒単位。
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhonemeSpan {
+    pub phoneme: String,
+    pub start: f32,
+    pub end: f32,
+    /// 属しているアクセント句のインデックス。前後の無音区間では`None`。
+    pub accent_phrase: Option<usize>,
+}
+
+/// AudioQueryから音素ごとの区間を計算する。合成は行わない。
+///
+/// 長さは本家と同じく`speed_scale`で割ったものを使う。
+pub fn phoneme_timeline(query: &AudioQuery) -> Vec<PhonemeSpan> {
+    let speed_scale = if query.speed_scale > 0.0 {
+        query.speed_scale
+    } else {
+        1.0
+    };
+    let mut spans = vec![];
+    let mut cursor = 0.0;
+    let mut push = |phoneme: &str, length: f32, accent_phrase: Option<usize>| {
+        let length = length.max(0.0) / speed_scale;
+        spans.push(PhonemeSpan {
+            phoneme: phoneme.to_string(),
+            start: cursor,
+            end: cursor + length,
+            accent_phrase,
+        });
+        cursor += length;
+    };
+
+    push("pau", query.pre_phoneme_length, None);
+    for (i, accent_phrase) in query.accent_phrases.iter().enumerate() {
+        for mora in accent_phrase.moras.iter().chain(accent_phrase.pause_mora.iter()) {
+            push_mora(&mut push, mora, i);
+        }
+    }
+    push("pau", query.post_phoneme_length, None);
+
+    spans
+}
+
+/// AudioQueryを合成したときの長さ（秒）。
+pub fn duration(query: &AudioQuery) -> f32 {
+    phoneme_timeline(query).last().map_or(0.0, |span| span.end)
+}
+
+fn push_mora(push: &mut impl FnMut(&str, f32, Option<usize>), mora: &Mora, accent_phrase: usize) {
+    if let (Some(consonant), Some(consonant_length)) = (&mora.consonant, mora.consonant_length) {
+        push(consonant, consonant_length, Some(accent_phrase));
+    }
+    push(&mora.vowel, mora.vowel_length, Some(accent_phrase));
+}
@@ -12,3 +12,29 @@ pub fn image_to_base64(image: &image::RgbaImage) -> String {
 pub fn process_dir() -> std::path::PathBuf {
     std::path::PathBuf::from(process_path::get_executable_path().unwrap().parent().unwrap())
 }
+
+/// 文字単位のレーベンシュタイン距離。
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// 半角英数字を全角にする。ユーザー辞書の表記は全角で保存されている。
+pub fn to_full_width(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '!'..='~' => char::from_u32(c as u32 + 0xFEE0).unwrap(),
+            _ => c,
+        })
+        .collect()
+}
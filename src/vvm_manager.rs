@@ -1,3 +1,5 @@
+use crate::utils::edit_distance;
+
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, OnceLock};
 use tokio::sync::Mutex;
@@ -92,6 +94,55 @@ impl VvmManager {
             .find(|&speakers| speakers.speaker_uuid == speaker_uuid)
     }
 
+    /// `キャラクター名/スタイル名`、`キャラクター名`（最初のスタイル）、スタイルIDのいずれかからスタイルIDを探す。
+    pub fn find_style(&self, name: &str) -> Option<u32> {
+        if let Ok(id) = name.parse::<u32>() {
+            return self.styles().any(|(_, style)| style.id() == id).then_some(id);
+        }
+        let (speaker_name, style_name) = match name.split_once('/') {
+            Some((speaker_name, style_name)) => (speaker_name, Some(style_name)),
+            None => (name, None),
+        };
+        let speaker = self.speakers.iter().find(|speaker| speaker.name == speaker_name)?;
+        Self::find_style_of(speaker, style_name)
+    }
+
+    /// キャラクターのUUIDとスタイル名からスタイルIDを探す。スタイル名が無い場合は最初のスタイルを返す。
+    pub fn find_style_by_uuid(&self, speaker_uuid: &str, style_name: Option<&str>) -> Option<u32> {
+        Self::find_style_of(self.speaker(speaker_uuid)?, style_name)
+    }
+
+    fn find_style_of(speaker: &SpeakerMeta, style_name: Option<&str>) -> Option<u32> {
+        match style_name {
+            Some(style_name) => speaker.styles.iter().find(|style| style.name() == style_name),
+            None => speaker.styles.first(),
+        }
+        .map(|style| style.id())
+    }
+
+    /// 全てのスタイルをキャラクターと一緒に返す。
+    pub fn styles(&self) -> impl Iterator<Item = (&SpeakerMeta, &StyleMeta)> {
+        self.speakers
+            .iter()
+            .flat_map(|speaker| speaker.styles.iter().map(move |style| (speaker, style)))
+    }
+
+    /// 名前が近い順に`キャラクター名/スタイル名`を返す。
+    pub fn suggest_styles(&self, name: &str, limit: usize) -> Vec<String> {
+        let threshold = (name.chars().count() / 2).max(2);
+        let mut candidates: Vec<(usize, String)> = self
+            .styles()
+            .map(|(speaker, style)| {
+                let label = format!("{}/{}", speaker.name, style.name());
+                let distance = edit_distance(name, &label).min(edit_distance(name, &speaker.name));
+                (distance, label)
+            })
+            .filter(|(distance, _)| *distance <= threshold)
+            .collect();
+        candidates.sort();
+        candidates.into_iter().take(limit).map(|(_, label)| label).collect()
+    }
+
     pub fn vvms(&self) -> &Vec<VoiceModel> {
         &self.vvms
     }
@@ -0,0 +1,113 @@
+use anyhow::{bail, Context};
+
+/// WAVの`fmt `チャンクのうち、結合に必要な部分。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavFormat {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+}
+
+impl WavFormat {
+    fn block_align(&self) -> u16 {
+        self.channels * self.bits_per_sample / 8
+    }
+}
+
+/// RIFF WAVを読み、フォーマットと`data`チャンクの中身を返す。
+pub fn parse(wav: &[u8]) -> anyhow::Result<(WavFormat, &[u8])> {
+    if wav.len() < 12 || &wav[0..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        bail!("WAVファイルではありません。");
+    }
+    let mut format = None;
+    let mut cursor = 12;
+    while cursor + 8 <= wav.len() {
+        let id = &wav[cursor..cursor + 4];
+        let size = u32::from_le_bytes(wav[cursor + 4..cursor + 8].try_into()?) as usize;
+        let body = wav
+            .get(cursor + 8..cursor + 8 + size)
+            .or_else(|| (id == b"data").then(|| &wav[cursor + 8..]))
+            .context("WAVファイルが壊れています。")?;
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                format = Some(WavFormat {
+                    channels: u16::from_le_bytes(body[2..4].try_into()?),
+                    sample_rate: u32::from_le_bytes(body[4..8].try_into()?),
+                    bits_per_sample: u16::from_le_bytes(body[14..16].try_into()?),
+                });
+            }
+            b"data" => {
+                let format = format.context("fmtチャンクがありません。")?;
+                return Ok((format, body));
+            }
+            _ => {}
+        }
+        cursor += 8 + size + size % 2;
+    }
+    bail!("dataチャンクがありません。")
+}
+
+/// PCMデータからWAVを作る。
+pub fn write(format: WavFormat, data: &[u8]) -> Vec<u8> {
+    let mut wav = Vec::with_capacity(44 + data.len());
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&format.channels.to_le_bytes());
+    wav.extend_from_slice(&format.sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(format.sample_rate * format.block_align() as u32).to_le_bytes());
+    wav.extend_from_slice(&format.block_align().to_le_bytes());
+    wav.extend_from_slice(&format.bits_per_sample.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    wav.extend_from_slice(data);
+    wav
+}
+
+/// 複数のWAVを順に繋げる。`gaps[i]`秒の無音を`i`番目の後に挟む。フォーマットは揃っている必要がある。
+pub fn concat(wavs: &[Vec<u8>], gaps: &[f32]) -> anyhow::Result<Vec<u8>> {
+    let mut format = None;
+    let mut data = vec![];
+    for (i, wav) in wavs.iter().enumerate() {
+        let (this_format, this_data) = parse(wav)?;
+        match format {
+            None => format = Some(this_format),
+            Some(format) if format != this_format => bail!("WAVのフォーマットが一致しません。"),
+            _ => {}
+        }
+        data.extend_from_slice(this_data);
+        if let Some(gap) = gaps.get(i).filter(|gap| **gap > 0.0) {
+            let frames = (gap * this_format.sample_rate as f32).round() as usize;
+            data.resize(data.len() + frames * this_format.block_align() as usize, 0);
+        }
+    }
+    let format = format.context("WAVがありません。")?;
+    Ok(write(format, &data))
+}
+
+/// WAVの長さ（秒）。
+pub fn duration(wav: &[u8]) -> anyhow::Result<f32> {
+    let (format, data) = parse(wav)?;
+    Ok(data.len() as f32 / format.block_align() as f32 / format.sample_rate as f32)
+}
+
+/// 16bit PCMのWAVをモノラルの`f32`列にする。
+pub fn samples(wav: &[u8]) -> anyhow::Result<(u32, Vec<f32>)> {
+    let (format, data) = parse(wav)?;
+    if format.bits_per_sample != 16 || format.channels == 0 {
+        bail!("16bit PCMのWAVのみに対応しています。");
+    }
+    let samples = data
+        .chunks_exact(format.block_align() as usize)
+        .map(|frame| {
+            let sum: f32 = frame
+                .chunks_exact(2)
+                .map(|sample| i16::from_le_bytes([sample[0], sample[1]]) as f32 / 32768.0)
+                .sum();
+            sum / format.channels as f32
+        })
+        .collect();
+    Ok((format.sample_rate, samples))
+}